@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Close Completely
+// ============================================================================
+//
+// WHAT'S FIXED:
+// Besides draining the lamports, this version wipes the account:
+// - Every data byte is zeroed, so no stale state is left to read
+// - The first 8 bytes are set to CLOSED_ACCOUNT_DISCRIMINATOR, which no
+//   `#[account]` type has
+//
+// A rent refund can still keep the account alive, but what comes back is a
+// closed marker, not a UserAccount: `Account<'info, UserAccount>` and
+// `try_deserialize` both reject it with AccountDiscriminatorMismatch.
+//
+// WHY A RAW ACCOUNT:
+// The account is taken as an AccountInfo and checked by hand. An
+// `Account<'info, UserAccount>` marked `mut` is serialized back when the
+// instruction exits, which would write the old UserAccount straight over
+// the wiped data.
+//
+// BEST PRACTICES:
+// 1. Prefer Anchor's `#[account(mut, close = destination)]`, which also
+//    hands the account back to the System Program and frees its data
+// 2. When closing by hand, drain, zero the data AND mark it closed
+// 3. Never let an instruction accept an account whose data could be stale
+//
+// ============================================================================
+
+declare_id!("AccountC1osing11111111111111111111111111111");
+
+/// Marks a closed account; matches Anchor's own closed-account marker
+pub const CLOSED_ACCOUNT_DISCRIMINATOR: [u8; 8] = [255; 8];
+
+#[program]
+pub mod account_closing_secure {
+    use super::*;
+
+    /// SECURE: Close by draining lamports and wiping the data
+    pub fn close_secure(ctx: Context<CloseSecure>) -> Result<()> {
+        let account = &ctx.accounts.user_account;
+        let destination = &ctx.accounts.destination;
+
+        // SECURE: The checks `Account<'info, UserAccount>` + has_one would make
+        require_keys_eq!(*account.owner, crate::ID, SecurityError::WrongAccountOwner);
+        let state = UserAccount::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+        require_keys_eq!(state.owner, ctx.accounts.owner.key(), SecurityError::Unauthorized);
+
+        let lamports = account.lamports();
+        **destination.lamports.borrow_mut() = destination
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+        **account.lamports.borrow_mut() = 0;
+
+        // SECURE: Nothing of the old state survives a refund
+        let mut data = account.try_borrow_mut_data()?;
+        data.fill(0);
+        data[..8].copy_from_slice(&CLOSED_ACCOUNT_DISCRIMINATOR);
+
+        msg!("Closed account of {}, refunded {} lamports", state.owner, lamports);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseSecure<'info> {
+    /// SECURE: Owner, type and authority checked in the handler
+    #[account(mut)]
+    pub user_account: AccountInfo<'info>,
+
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub destination: AccountInfo<'info>,
+}
+
+#[account]
+pub struct UserAccount {
+    pub owner: Pubkey,
+    pub balance: u64,
+}
@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+
+// ============================================================================
+// VULNERABILITY: Account Revival After Close
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// This program "closes" an account by moving all of its lamports out and
+// nothing else. The data - discriminator included - stays where it was,
+// and the account is still owned by this program.
+//
+// The runtime only deletes an account with zero lamports at the END of the
+// transaction. Until then it is an ordinary account, and anyone can send
+// lamports back to it:
+//
+//     ix 1: close_vulnerable      -> lamports drained, data untouched
+//     ix 2: system transfer       -> rent refunded to the account
+//     end of transaction          -> rent-exempt again, so it survives
+//
+// The account is back with its old contents, as if it had never been closed.
+//
+// WHY IT'S UNSAFE:
+// - Anything that treated the close as final (a refund paid out, a position
+//   settled, a one-time record consumed) can be replayed against the account
+// - The revived account still passes every Anchor check for its type
+// - The rent refund costs the attacker nothing: it stays in the account
+//
+// SEVERITY: HIGH
+// ============================================================================
+
+declare_id!("AccountC1osing11111111111111111111111111111");
+
+#[program]
+pub mod account_closing {
+    use super::*;
+
+    /// VULNERABLE: Close by draining lamports only
+    pub fn close_vulnerable(ctx: Context<CloseVulnerable>) -> Result<()> {
+        let account = ctx.accounts.user_account.to_account_info();
+        let destination = ctx.accounts.destination.to_account_info();
+
+        // VULNERABILITY: Lamports move out, but the data and discriminator
+        // are left in place for a refund to bring back to life
+        let lamports = account.lamports();
+        **destination.lamports.borrow_mut() = destination
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(CustomError::ArithmeticOverflow)?;
+        **account.lamports.borrow_mut() = 0;
+
+        msg!("Closed account of {}, refunded {} lamports", ctx.accounts.owner.key(), lamports);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseVulnerable<'info> {
+    #[account(mut, has_one = owner)]
+    pub user_account: Account<'info, UserAccount>,
+
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub destination: AccountInfo<'info>,
+}
+
+#[account]
+pub struct UserAccount {
+    pub owner: Pubkey,
+    pub balance: u64,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+}
@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use security_utils::TokenOps;
+
+// ============================================================================
+// FIX: Canonical Bumps Only
+// ============================================================================
+//
+// WHAT'S FIXED:
+// The claim record is created with `init` and a bare `bump` constraint.
+// Anchor derives the address with `find_program_address`, so each user has
+// exactly one claim record - at the canonical bump - and `init` fails if it
+// already exists. Any other address, shadow PDAs included, fails the seeds
+// check with ConstraintSeeds. The caller never supplies a bump at all.
+//
+// The canonical bump is stored in the record, so later instructions can
+// use `bump = claim.bump` without searching for it again.
+//
+// BEST PRACTICES:
+// 1. Never take a bump from instruction data
+// 2. Use `bump` on init, and `bump = account.bump` (the stored canonical
+//    bump) afterwards
+// 3. Outside Anchor constraints, derive with `find_program_address`, not
+//    `create_program_address` with an untrusted bump
+//
+// ============================================================================
+
+declare_id!("BumpSeedCanonica1ization1111111111111111111");
+
+/// Tokens minted per claim
+pub const AIRDROP_AMOUNT: u64 = 1_000;
+
+#[program]
+pub mod bump_seed_canonicalization_secure {
+    use super::*;
+
+    /// SECURE: Claim the airdrop, recorded at the canonical PDA
+    pub fn claim_safe(ctx: Context<ClaimSafe>) -> Result<()> {
+        let claim = &mut ctx.accounts.claim;
+        claim.claimant = ctx.accounts.user.key();
+        claim.bump = ctx.bumps.claim;
+
+        let authority_seeds: &[&[u8]] = &[b"mint_authority", &[ctx.bumps.mint_authority]];
+        ctx.accounts.token_program.mint_to(
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.user_token.to_account_info(),
+            ctx.accounts.mint_authority.to_account_info(),
+            AIRDROP_AMOUNT,
+            &[authority_seeds],
+        )?;
+
+        msg!("Airdropped {} tokens to {}", AIRDROP_AMOUNT, claim.claimant);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimSafe<'info> {
+    /// SECURE: Canonical bump found by Anchor; init fails on a second claim
+    #[account(
+        init,
+        payer = user,
+        space = ClaimRecord::SPACE,
+        seeds = [b"claim", user.key().as_ref()],
+        bump,
+    )]
+    pub claim: Account<'info, ClaimRecord>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut, token::mint = mint)]
+    pub user_token: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimRecord {
+    pub claimant: Pubkey,
+    pub bump: u8,
+}
+
+impl ClaimRecord {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
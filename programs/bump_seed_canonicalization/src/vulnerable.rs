@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{program::invoke_signed, system_instruction};
+use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount};
+
+// ============================================================================
+// VULNERABILITY: Non-Canonical Bump Seeds
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// This program hands out a one-time airdrop and records each claim in a PDA
+// at ["claim", user, bump]. It takes the bump from the caller and only
+// checks that `create_program_address` accepts it.
+//
+// A seed set has up to 255 valid bumps, not one. `find_program_address`
+// returns the first (highest) one that lands off the curve - the canonical
+// bump - but roughly half of all the lower bumps are valid PDAs too, each at
+// a different address:
+//
+//     ["claim", user, 255]  ->  claim record A  (canonical)
+//     ["claim", user, 253]  ->  claim record B  (shadow)
+//     ["claim", user, 252]  ->  claim record C  (shadow)
+//
+// WHY IT'S UNSAFE:
+// - "One claim per user" becomes "one claim per valid bump"
+// - Every shadow PDA is a fresh, empty claim record, so the airdrop mints again
+// - Any uniqueness or lookup keyed on a PDA has the same problem
+//
+// SEVERITY: HIGH
+// ============================================================================
+
+declare_id!("BumpSeedCanonica1ization1111111111111111111");
+
+/// Tokens minted per claim
+pub const AIRDROP_AMOUNT: u64 = 1_000;
+
+#[program]
+pub mod bump_seed_canonicalization {
+    use super::*;
+
+    /// VULNERABLE: Claim the airdrop, recorded at a caller-chosen bump
+    pub fn claim_unsafe(ctx: Context<ClaimUnsafe>, bump: u8) -> Result<()> {
+        let user_key = ctx.accounts.user.key();
+        let claim_seeds: &[&[u8]] = &[b"claim", user_key.as_ref(), &[bump]];
+
+        // VULNERABILITY: Any bump that yields a valid PDA is accepted - not
+        // just the canonical one - so each user has many claim addresses
+        let expected = Pubkey::create_program_address(claim_seeds, &crate::ID)
+            .map_err(|_| CustomError::InvalidClaimAddress)?;
+        require_keys_eq!(ctx.accounts.claim.key(), expected, CustomError::InvalidClaimAddress);
+
+        // Looks like a double-claim check, but only for this one address
+        require!(ctx.accounts.claim.lamports() == 0, CustomError::AlreadyClaimed);
+
+        let space = ClaimRecord::SPACE;
+        invoke_signed(
+            &system_instruction::create_account(
+                &user_key,
+                &expected,
+                Rent::get()?.minimum_balance(space),
+                space as u64,
+                &crate::ID,
+            ),
+            &[
+                ctx.accounts.user.to_account_info(),
+                ctx.accounts.claim.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[claim_seeds],
+        )?;
+        let record = ClaimRecord {
+            claimant: user_key,
+            bump,
+        };
+        record.try_serialize(&mut &mut ctx.accounts.claim.try_borrow_mut_data()?[..])?;
+
+        let authority_seeds: &[&[u8]] = &[b"mint_authority", &[ctx.bumps.mint_authority]];
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.user_token.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                &[authority_seeds],
+            ),
+            AIRDROP_AMOUNT,
+        )?;
+
+        msg!("Airdropped {} tokens to {} (bump {})", AIRDROP_AMOUNT, user_key, bump);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimUnsafe<'info> {
+    /// VULNERABILITY: Address checked against a caller-supplied bump
+    #[account(mut)]
+    pub claim: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut, token::mint = mint)]
+    pub user_token: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimRecord {
+    pub claimant: Pubkey,
+    pub bump: u8,
+}
+
+impl ClaimRecord {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Claim account does not match the seeds and bump")]
+    InvalidClaimAddress,
+
+    #[msg("Airdrop already claimed")]
+    AlreadyClaimed,
+}
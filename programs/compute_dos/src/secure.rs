@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{compute_units::sol_remaining_compute_units, hash::hashv};
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Bounded Compute
+// ============================================================================
+//
+// WHAT'S FIXED:
+// This version keeps the amount of work per instruction under control:
+// - The caller-supplied round count is capped by a constant
+// - Each round first checks the remaining compute budget, and bails out
+//   with a clear error while there is still enough left to fail cleanly
+//
+// BEST PRACTICES:
+// 1. Never loop a caller-controlled number of times without a cap
+// 2. Size the cap so the worst case fits the default compute budget
+// 3. Check sol_remaining_compute_units() inside long loops
+// 4. Split large jobs across transactions instead of one giant one
+//
+// ============================================================================
+
+declare_id!("99999999999999999999999999999999");
+
+/// Most rounds a single instruction will run
+pub const MAX_ROUNDS: u64 = 1_000;
+
+/// Compute kept in reserve for one more round plus writing the account back
+pub const MIN_REMAINING_COMPUTE: u64 = 10_000;
+
+#[program]
+pub mod compute_dos_secure {
+    use super::*;
+
+    /// SECURE: Extend the hash chain by a bounded number of rounds
+    pub fn hash_rounds_safe(
+        ctx: Context<HashRoundsSafe>,
+        rounds: u64,
+    ) -> Result<()> {
+        // SECURE: Reject oversized jobs before doing any work
+        require!(rounds <= MAX_ROUNDS, CustomError::ComputeBudgetExceeded);
+
+        let chain = &mut ctx.accounts.chain;
+
+        for round in 0..rounds {
+            // SECURE: Stop while we can still return a meaningful error,
+            // instead of letting the runtime abort us mid-loop
+            require!(
+                sol_remaining_compute_units() >= MIN_REMAINING_COMPUTE,
+                CustomError::ComputeBudgetExceeded
+            );
+            chain.hash = hashv(&[&chain.hash, &round.to_le_bytes()]).to_bytes();
+        }
+        chain.rounds = chain
+            .rounds
+            .checked_add(rounds)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+
+        msg!("Hashed {} rounds", rounds);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct HashRoundsSafe<'info> {
+    #[account(mut)]
+    pub chain: Account<'info, HashChain>,
+}
+
+#[account]
+pub struct HashChain {
+    pub hash: [u8; 32],
+    pub rounds: u64,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Requested work would exceed the compute budget")]
+    ComputeBudgetExceeded,
+}
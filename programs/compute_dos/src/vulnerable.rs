@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+// ============================================================================
+// VULNERABILITY: Unbounded Compute
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// This program runs a loop whose length comes straight from instruction
+// data. Every transaction has a fixed compute budget (200k units per
+// instruction by default, 1.4M max), and a loop that runs past it aborts
+// the whole transaction after burning the fee.
+//
+// WHY IT'S UNSAFE:
+// - The caller, not the program, decides how much work is done
+// - A large count fails with an opaque "exceeded CUs" error, deep in the loop
+// - Anything composing with this instruction (a crank, a keeper, another
+//   program passing a user's count through a CPI) can be made to fail at will
+// - The same pattern over stored lists or remaining_accounts lets one
+//   user grow the work until nobody's transaction fits
+//
+// SEVERITY: MEDIUM
+// ============================================================================
+
+declare_id!("99999999999999999999999999999999");
+
+#[program]
+pub mod compute_dos {
+    use super::*;
+
+    /// VULNERABLE: Extend the hash chain by a caller-chosen number of rounds
+    pub fn hash_rounds_unsafe(
+        ctx: Context<HashRoundsUnsafe>,
+        rounds: u64,
+    ) -> Result<()> {
+        let chain = &mut ctx.accounts.chain;
+
+        // VULNERABILITY: No upper bound on `rounds` and no check of the
+        // remaining compute budget - the runtime kills the transaction
+        // wherever the budget happens to run out
+        for round in 0..rounds {
+            chain.hash = hashv(&[&chain.hash, &round.to_le_bytes()]).to_bytes();
+        }
+        chain.rounds = chain.rounds.wrapping_add(rounds);
+
+        msg!("Hashed {} rounds", rounds);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct HashRoundsUnsafe<'info> {
+    #[account(mut)]
+    pub chain: Account<'info, HashChain>,
+}
+
+#[account]
+pub struct HashChain {
+    pub hash: [u8; 32],
+    pub rounds: u64,
+}
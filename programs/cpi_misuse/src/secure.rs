@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token::{Token, TokenAccount};
 
 // ============================================================================
 // FIX: Safe CPI Patterns
@@ -10,11 +10,12 @@ use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 // - Verifies the program being called is the expected program
 // - Uses Anchor's CPI helpers instead of raw invoke
 // - Checks return values from CPI calls
+// - Accepts return data only from the program it called
 // - Proper signer delegation with seeds
 //
 // BEST PRACTICES:
 // 1. Always verify program IDs match constants
-// 2. Use CpiContext for high-level helpers
+// 2. Use validated helpers (security_utils::TokenOps) for token CPIs
 // 3. Check return values from all CPI calls
 // 4. Use PDA seeds for signer delegation
 // 5. Validate account ownership and state before CPI
@@ -22,13 +23,17 @@ use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 // ============================================================================
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::pubkey;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Token, TokenAccount};
+use security_utils::{pda, SecurityError, TokenOps};
 
 declare_id!("44444444444444444444444444444444");
 
 // SECURE: Define known trusted program IDs as constants
 // This prevents attacker from passing arbitrary program IDs
-const TRUSTED_PROGRAM_ID: &str = "11111111111111111111111111111111"; // Example trusted program
+// Example trusted program (the System Program) - change this to your own
+pub const TRUSTED_PROGRAM_ID: Pubkey = pubkey!("11111111111111111111111111111111");
 
 #[program]
 pub mod cpi_misuse_secure {
@@ -39,26 +44,15 @@ pub mod cpi_misuse_secure {
         ctx: Context<TransferSafeCpi>,
         amount: u64,
     ) -> Result<()> {
-        // SECURE: Verify this is the actual token program
-        // by checking against a known constant
-        require_eq!(
-            ctx.accounts.token_program.key(),
-            spl_token::id(),
-            CustomError::InvalidTokenProgram
-        );
-
-        // SECURE: Use Anchor's CpiContext which handles the invoke for us
-        // and ensures proper account validation
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.from_token.to_account_info(),
-                    to: ctx.accounts.to_token.to_account_info(),
-                    authority: ctx.accounts.authority.to_account_info(),
-                },
-            ),
+        // SECURE: The address constraint rejects any other program with
+        // InvalidTokenProgram; TokenOps re-checks the id, checks that it is
+        // executable, and propagates the CPI result.
+        ctx.accounts.token_program.transfer(
+            ctx.accounts.from_token.to_account_info(),
+            ctx.accounts.to_token.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
             amount,
+            &[],
         )?; // SECURE: We check the Result from the CPI
 
         msg!("Token transfer completed successfully");
@@ -70,18 +64,43 @@ pub mod cpi_misuse_secure {
         ctx: Context<DeligateSafe>,
         instruction_data: Vec<u8>,
     ) -> Result<()> {
+        // SECURE: Bound the payload before doing anything with it.
+        // Oversized data wastes compute on copying and can push the CPI
+        // past runtime limits, so reject it up front.
+        require!(
+            instruction_data.len() <= MAX_DELEGATE_DATA_LEN,
+            CustomError::InstructionDataTooLarge
+        );
+
+        // SECURE: The same account must not appear as both the writable
+        // user_data and the read-only owner. Aliased metas with conflicting
+        // mutability let the callee write through what we passed as read-only.
+        require_keys_neq!(
+            ctx.accounts.user_data.key(),
+            ctx.accounts.owner.key(),
+            CustomError::AliasedAccount
+        );
+
+        // SECURE: target_program is a plain AccountInfo, so nothing else
+        // guarantees it is a program. A data account in this slot is a
+        // strong sign someone is probing the handler.
+        require!(
+            ctx.accounts.target_program.executable,
+            SecurityError::ProgramNotExecutable
+        );
+
         // SECURE: Verify the target program is one we expect
         require_keys_eq!(
             ctx.accounts.target_program.key(),
             TRUSTED_PROGRAM_ID, // Must be a constant defined by us
-            CustomError::UntrustedProgram
+            SecurityError::UntrustedProgram
         );
 
         // SECURE: Verify the user_data account is owned by the target program
         require_keys_eq!(
-            ctx.accounts.user_data.owner,
+            *ctx.accounts.user_data.owner,
             TRUSTED_PROGRAM_ID,
-            CustomError::WrongAccountOwner
+            SecurityError::WrongAccountOwner
         );
 
         // Construct instruction safely
@@ -117,6 +136,49 @@ pub mod cpi_misuse_secure {
         }
     }
 
+    /// SECURE: Reads a u64 result, accepted only from the program called
+    ///
+    /// Accounts after the named ones are forwarded to the callee.
+    pub fn safe_cpi_with_return_data<'info>(
+        ctx: Context<'_, '_, '_, 'info, CpiWithReturnDataSafe<'info>>,
+    ) -> Result<()> {
+        let target_program = &ctx.accounts.target_program;
+        require!(target_program.executable, SecurityError::ProgramNotExecutable);
+
+        let instruction = anchor_lang::solana_program::instruction::Instruction {
+            program_id: target_program.key(),
+            accounts: ctx
+                .remaining_accounts
+                .iter()
+                .map(|account| AccountMeta::new_readonly(account.key(), false))
+                .collect(),
+            data: vec![],
+        };
+        let mut infos = ctx.remaining_accounts.to_vec();
+        infos.push(target_program.to_account_info());
+        anchor_lang::solana_program::program::invoke(&instruction, &infos)?;
+
+        // SECURE: Return data survives the CPI that set it, so it may come
+        // from further down the call stack. Only the callee's own answer counts.
+        let (program_id, data) = anchor_lang::solana_program::program::get_return_data()
+            .ok_or(CustomError::MissingReturnData)?;
+        require_keys_eq!(
+            program_id,
+            target_program.key(),
+            CustomError::ReturnDataFromWrongProgram
+        );
+
+        // SECURE: Exactly one u64 - no shorter, no trailing bytes
+        let bytes: [u8; 8] = data
+            .as_slice()
+            .try_into()
+            .map_err(|_| CustomError::InvalidReturnData)?;
+        let value = u64::from_le_bytes(bytes);
+
+        msg!("Callee returned {}", value);
+        Ok(())
+    }
+
     /// SECURE: CPI with PDA signer delegation
     pub fn safe_delegate_with_pda(
         ctx: Context<DelegateWithPda>,
@@ -124,38 +186,82 @@ pub mod cpi_misuse_secure {
         amount: u64,
     ) -> Result<()> {
         // SECURE: Verify the PDA was derived correctly
-        let seeds = b"trusted_seed".as_ref();
-        let pda = Pubkey::find_program_address(&[seeds], &crate::ID()).0;
-        
+        let (pda, canonical_bump) = pda::derive(TRUSTED_SIGNER_NAMESPACE, &ID, &ID)?;
+
         require_keys_eq!(
             ctx.accounts.pda_signer.key(),
             pda,
             CustomError::InvalidPdaSigner
         );
 
+        // SECURE: The caller-supplied bump is what we sign with below.
+        // Any other bump signs as a different address, so reject it here
+        // with a clear error instead of failing deep inside the token CPI.
+        require_eq!(bump, canonical_bump, CustomError::InvalidPdaSigner);
+
         // SECURE: Use PDA as signer in CPI
-        let signer_seeds: &[&[&[u8]]] = &[&[b"trusted_seed".as_ref(), &[bump]]];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[TRUSTED_SIGNER_NAMESPACE.as_bytes(), ID.as_ref(), &[bump]]];
 
-        token::transfer(
+        ctx.accounts.token_program.transfer(
+            ctx.accounts.from_token.to_account_info(),
+            ctx.accounts.to_token.to_account_info(),
+            ctx.accounts.pda_signer.to_account_info(),
+            amount,
+            signer_seeds,
+        )?;
+
+        msg!("PDA-signed transfer completed successfully");
+        Ok(())
+    }
+
+    /// SECURE: Pay SOL out of the caller's vault PDA via the System Program
+    ///
+    /// The vault at `[VAULT_NAMESPACE, user]` is a plain system account: only the
+    /// System Program can debit it, and only with this program signing for
+    /// the PDA. The System Program moves exactly `amount` from one account
+    /// to the other, so lamports are conserved by construction.
+    pub fn withdraw_from_vault_safe(
+        ctx: Context<WithdrawFromVaultSafe>,
+        amount: u64,
+    ) -> Result<()> {
+        // SECURE: Checked math - the vault can't pay out more than it holds
+        ctx.accounts
+            .vault
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(CustomError::InsufficientPdaLamports)?;
+
+        let user_key = ctx.accounts.user.key();
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[VAULT_NAMESPACE.as_bytes(), user_key.as_ref(), &[ctx.bumps.vault]]];
+
+        system_program::transfer(
             CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.from_token.to_account_info(),
-                    to: ctx.accounts.to_token.to_account_info(),
-                    authority: ctx.accounts.pda_signer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.user.to_account_info(),
                 },
                 signer_seeds,
             ),
             amount,
         )?;
 
-        msg!("PDA-signed transfer completed successfully");
+        msg!("Withdrew {} lamports from vault", amount);
         Ok(())
     }
 }
 
-// Known trusted program - change this to your actual trusted program
-pub const TRUSTED_PROGRAM_ID: Pubkey = Pubkey::new_from_array([0; 32]); // Placeholder
+// Largest instruction payload we are willing to forward in a delegated call
+pub const MAX_DELEGATE_DATA_LEN: usize = 512;
+
+// PDA namespace of the program-wide delegation signer:
+// `[TRUSTED_SIGNER_NAMESPACE, ID]`
+pub const TRUSTED_SIGNER_NAMESPACE: &str = "cpi_misuse/trusted_signer";
+
+// PDA namespace of a user's SOL vault: `[VAULT_NAMESPACE, user]`
+pub const VAULT_NAMESPACE: &str = "cpi_misuse/vault";
 
 #[derive(Accounts)]
 pub struct TransferSafeCpi<'info> {
@@ -167,8 +273,9 @@ pub struct TransferSafeCpi<'info> {
     
     pub authority: Signer<'info>,
     
-    /// SECURE: We verify this is the token program
-    pub token_program: Program<'info, Token>,
+    /// SECURE: We verify this is the token program, with a named error
+    #[account(address = token::ID @ CustomError::InvalidTokenProgram)]
+    pub token_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
@@ -182,6 +289,12 @@ pub struct DeligateSafe<'info> {
     pub target_program: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CpiWithReturnDataSafe<'info> {
+    /// SECURE: The only program whose return data we accept
+    pub target_program: AccountInfo<'info>,
+}
+
 #[derive(Accounts)]
 pub struct DelegateWithPda<'info> {
     #[account(mut)]
@@ -196,20 +309,44 @@ pub struct DelegateWithPda<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct WithdrawFromVaultSafe<'info> {
+    /// SECURE: Must be the caller's own vault PDA
+    #[account(mut, seeds = [VAULT_NAMESPACE.as_bytes(), user.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[error_code]
 pub enum CustomError {
     #[msg("Invalid token program")]
     InvalidTokenProgram,
     
-    #[msg("Untrusted program")]
-    UntrustedProgram,
-    
-    #[msg("Wrong account owner")]
-    WrongAccountOwner,
-    
     #[msg("CPI execution failed")]
     CpiFailed,
     
     #[msg("Invalid PDA signer")]
     InvalidPdaSigner,
+
+    #[msg("Instruction data exceeds the maximum allowed length")]
+    InstructionDataTooLarge,
+
+    #[msg("The same account was passed in more than one position")]
+    AliasedAccount,
+
+    #[msg("Vault PDA holds fewer lamports than requested")]
+    InsufficientPdaLamports,
+
+    #[msg("Callee returned no data")]
+    MissingReturnData,
+
+    #[msg("Return data was set by a program other than the callee")]
+    ReturnDataFromWrongProgram,
+
+    #[msg("Return data is not a single u64")]
+    InvalidReturnData,
 }
@@ -11,6 +11,7 @@ use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 // - Signer delegation verification
 // - Return value checking
 // - Account mutability requirements
+// - A check of which program set the return data it reads
 //
 // WHY IT'S UNSAFE:
 // - Doesn't verify which program is being called
@@ -35,6 +36,7 @@ pub mod cpi_misuse {
         // VULNERABILITY: We don't verify token_program is actually
         // the legitimate Solana token program!
         // An attacker could pass a fake program and steal tokens
+        // VULNERABILITY: We don't even check that it is executable
         
         let transfer_instruction = anchor_lang::solana_program::instruction::Instruction {
             program_id: ctx.accounts.token_program.key(),
@@ -43,10 +45,18 @@ pub mod cpi_misuse {
                 AccountMeta::new(ctx.accounts.to_token.key(), false),
                 AccountMeta::new_readonly(ctx.accounts.authority.key(), true),
             ],
-            data: vec![], // VULNERABILITY: Not properly formatted
+            // VULNERABILITY: Not properly formatted. Empty data isn't any
+            // token instruction (a Transfer is tag 3 + the amount as u64 LE),
+            // so the real token program rejects it and nothing ever moves -
+            // `amount` isn't even sent. Anchor's CPI helpers encode this for you.
+            data: vec![],
         };
 
         // VULNERABILITY: We don't check the return value!
+        // On-chain a failed CPI aborts the whole transaction anyway, so the
+        // ignored error is never seen there. The danger is the assumption
+        // that the handler can carry on: harnesses that hand the error back
+        // (like the native test processor) report a "successful" transfer.
         let _result = anchor_lang::solana_program::program::invoke_signed(
             &transfer_instruction,
             &[
@@ -69,6 +79,10 @@ pub mod cpi_misuse {
     ) -> Result<()> {
         // VULNERABILITY: We blindly invoke ANY program with ANY data!
         // This is extremely dangerous - the attacker controls what code runs
+        // VULNERABILITY: No length limit on instruction_data either, so an
+        // arbitrarily large payload is copied and forwarded
+        // VULNERABILITY: Nothing stops user_data and owner from being the same
+        // account, so it is forwarded as both writable and read-only
         
         let instruction = anchor_lang::solana_program::instruction::Instruction {
             program_id: ctx.accounts.target_program.key(), // Could be ANYTHING
@@ -91,6 +105,61 @@ pub mod cpi_misuse {
         msg!("Executed arbitrary instruction!");
         Ok(())
     }
+
+    /// VULNERABLE: Reads a u64 result from the callee's return data
+    ///
+    /// Accounts after the named ones are forwarded to the callee.
+    pub fn unsafe_cpi_with_return_data<'info>(
+        ctx: Context<'_, '_, '_, 'info, CpiWithReturnDataUnsafe<'info>>,
+    ) -> Result<()> {
+        let instruction = anchor_lang::solana_program::instruction::Instruction {
+            program_id: ctx.accounts.target_program.key(),
+            accounts: ctx
+                .remaining_accounts
+                .iter()
+                .map(|account| AccountMeta::new_readonly(account.key(), false))
+                .collect(),
+            data: vec![],
+        };
+        let mut infos = ctx.remaining_accounts.to_vec();
+        infos.push(ctx.accounts.target_program.to_account_info());
+        anchor_lang::solana_program::program::invoke(&instruction, &infos)?;
+
+        // VULNERABILITY: Return data is one buffer per transaction, holding
+        // whatever the last program to call set_return_data left there. If
+        // target_program set nothing but called a program that did, this is
+        // that program's answer - and the program id that says so is dropped
+        let (_, data) = anchor_lang::solana_program::program::get_return_data()
+            .ok_or(CustomError::MissingReturnData)?;
+
+        // VULNERABILITY: Slicing panics on fewer than 8 bytes, and anything
+        // past the first 8 is silently ignored
+        let value = u64::from_le_bytes(data[..8].try_into().unwrap());
+
+        msg!("Callee returned {}", value);
+        Ok(())
+    }
+
+    /// VULNERABLE: Pay SOL out of a vault by editing lamports directly
+    pub fn withdraw_from_vault_unsafe(
+        ctx: Context<WithdrawFromVaultUnsafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let vault = ctx.accounts.vault.to_account_info();
+        let user = ctx.accounts.user.to_account_info();
+
+        // VULNERABILITY: Any program-owned account is accepted as the vault
+        // VULNERABILITY: The debit saturates at zero but the credit is the
+        // full amount, so asking for more than the vault holds creates
+        // lamports out of nothing. Only the runtime's balance check stops
+        // it, failing the instruction with an error that doesn't say why.
+        let remaining = vault.lamports().saturating_sub(amount);
+        **vault.try_borrow_mut_lamports()? = remaining;
+        **user.try_borrow_mut_lamports()? += amount;
+
+        msg!("Withdrew {} lamports from vault", amount);
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
@@ -119,8 +188,26 @@ pub struct DelegateUnsafe<'info> {
     pub target_program: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CpiWithReturnDataUnsafe<'info> {
+    /// VULNERABILITY: Whoever answers on its behalf is believed
+    pub target_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFromVaultUnsafe<'info> {
+    #[account(mut)]
+    pub vault: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
 #[error_code]
 pub enum CustomError {
     #[msg("CPI execution failed")]
     CpiFailed,
+
+    #[msg("Callee returned no data")]
+    MissingReturnData,
 }
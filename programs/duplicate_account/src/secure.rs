@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Distinct Accounts
+// ============================================================================
+//
+// WHAT'S FIXED:
+// This version refuses to run unless `from` and `to` are different
+// accounts, before either is touched. With distinct accounts each copy is
+// the only copy, so the debit and the credit both persist.
+//
+// BEST PRACTICES:
+// 1. Any two mutable accounts of the same type need a distinctness check
+// 2. Do it first: `require_keys_neq!`, or a
+//    `constraint = from.key() != to.key()` on the account
+// 3. Watch for less obvious pairs too - a fee account that can also be
+//    the user's account, or a vault passed as both source and treasury
+//
+// ============================================================================
+
+declare_id!("Dup1icateAccount111111111111111111111111111");
+
+#[program]
+pub mod duplicate_account_secure {
+    use super::*;
+
+    /// SECURE: Transfer between two balances that must be different accounts
+    pub fn transfer_secure(
+        ctx: Context<TransferSecure>,
+        amount: u64,
+    ) -> Result<()> {
+        // SECURE: One account passed twice is rejected outright
+        require_keys_neq!(
+            ctx.accounts.from.key(),
+            ctx.accounts.to.key(),
+            SecurityError::DuplicateAccount
+        );
+
+        let from = &mut ctx.accounts.from;
+        from.amount = from
+            .amount
+            .checked_sub(amount)
+            .ok_or(CustomError::InsufficientBalance)?;
+
+        let to = &mut ctx.accounts.to;
+        to.amount = to
+            .amount
+            .checked_add(amount)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+
+        msg!("Transferred {} from {} to {}", amount, ctx.accounts.from.key(), to.key());
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct TransferSecure<'info> {
+    #[account(mut, has_one = owner)]
+    pub from: Account<'info, Balance>,
+
+    /// SECURE: Checked to differ from `from` in the handler
+    #[account(mut)]
+    pub to: Account<'info, Balance>,
+
+    pub owner: Signer<'info>,
+}
+
+#[account]
+pub struct Balance {
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Insufficient balance")]
+    InsufficientBalance,
+}
@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+
+// ============================================================================
+// VULNERABILITY: Duplicate Mutable Accounts
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// This program moves an amount from one Balance account to another and
+// never checks that `from` and `to` are different accounts.
+//
+// Anchor deserializes each parameter separately, so passing the same
+// account twice gives the handler two independent copies of one balance.
+// Each copy is updated on its own, and when the instruction exits both are
+// written back to the same account, in declaration order - the last write
+// wins:
+//
+//     from == to, balance 100, transfer 100
+//     to.amount   = 100 + 100 = 200
+//     from.amount = 100 - 100 =   0
+//     exit: write from (0), then to (200)  ->  balance 200
+//
+// WHY IT'S UNSAFE:
+// - A self-transfer mints tokens out of nothing
+// - Every check on `from` passes - it really is the owner's account
+// - The same pattern breaks any instruction that reads one account and
+//   writes another, e.g. swaps, liquidations, reward claims
+//
+// SEVERITY: CRITICAL
+// ============================================================================
+
+declare_id!("Dup1icateAccount111111111111111111111111111");
+
+#[program]
+pub mod duplicate_account {
+    use super::*;
+
+    /// VULNERABLE: Transfer between two balances that may be the same account
+    pub fn transfer_vulnerable(
+        ctx: Context<TransferVulnerable>,
+        amount: u64,
+    ) -> Result<()> {
+        // VULNERABILITY: No check that from != to - with one account passed
+        // twice, these update two copies and only `to` survives
+        let to = &mut ctx.accounts.to;
+        to.amount = to
+            .amount
+            .checked_add(amount)
+            .ok_or(CustomError::ArithmeticOverflow)?;
+
+        let from = &mut ctx.accounts.from;
+        from.amount = from
+            .amount
+            .checked_sub(amount)
+            .ok_or(CustomError::InsufficientBalance)?;
+
+        msg!("Transferred {} from {} to {}", amount, from.key(), ctx.accounts.to.key());
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct TransferVulnerable<'info> {
+    #[account(mut, has_one = owner)]
+    pub from: Account<'info, Balance>,
+
+    /// VULNERABILITY: May be the same account as `from`
+    #[account(mut)]
+    pub to: Account<'info, Balance>,
+
+    pub owner: Signer<'info>,
+}
+
+#[account]
+pub struct Balance {
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Insufficient balance")]
+    InsufficientBalance,
+
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+}
@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+};
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use security_utils::{safe_math::safe_add, SecurityError};
+
+// ============================================================================
+// FIX: Balance Invariant Checked After the Callback
+// ============================================================================
+//
+// WHAT'S FIXED:
+// Repayment is proven by the vault itself. Before lending, the program
+// records the vault's balance; after the receiver returns, it re-reads the
+// vault and requires
+//
+//     vault.amount >= balance_before + fee
+//
+// If the receiver kept any of the loan or skipped the fee, the instruction
+// fails - and a failed instruction rolls back the whole transaction,
+// including the transfer that sent the funds out.
+//
+// `vault.reload()` matters: an `Account<'info, TokenAccount>` is a copy
+// deserialized when the instruction started. Without reloading, the check
+// would read the pre-loan balance and never see what the receiver did -
+// rejecting every loan here, and accepting every loan at a zero fee.
+//
+// BEST PRACTICES:
+// 1. Check invariants against on-chain state, never against caller claims
+// 2. Snapshot the balance before the external call, re-read it after
+// 3. `reload()` any account a CPI may have changed before trusting it
+// 4. Lend with `invoke`, not `invoke_signed`, so the vault's PDA authority
+//    never signs anything on the receiver's behalf
+//
+// ============================================================================
+
+declare_id!("F1ashLoan1111111111111111111111111111111111");
+
+/// Fee charged on every loan, in basis points of the amount
+pub const FLASH_LOAN_FEE_BPS: u64 = 9;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// PDA namespace of the vault authority: `[POOL_SIGNER_NAMESPACE, pool]`
+pub const POOL_SIGNER_NAMESPACE: &str = "flash_loan_invariant/pool_signer";
+
+#[program]
+pub mod flash_loan_invariant_secure {
+    use super::*;
+
+    /// SECURE: Lend `amount` to the borrower's receiver program
+    ///
+    /// Accounts after the named ones are forwarded to the receiver, which
+    /// must return `amount + fee` to the vault before it returns.
+    pub fn flash_loan_secure<'info>(
+        ctx: Context<'_, '_, '_, 'info, FlashLoanSecure<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let fee = loan_fee(amount)?;
+
+        // SECURE: Snapshot the invariant before any funds move
+        let required_balance = safe_add(ctx.accounts.vault.amount, fee)?;
+
+        let pool_key = ctx.accounts.pool.key();
+        let signer_bump = [ctx.accounts.pool.signer_bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[POOL_SIGNER_NAMESPACE.as_bytes(), pool_key.as_ref(), &signer_bump]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.borrower_token.to_account_info(),
+                    authority: ctx.accounts.pool_signer.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        call_receiver(
+            &ctx.accounts.receiver_program,
+            ctx.remaining_accounts,
+            amount,
+            fee,
+        )?;
+
+        // SECURE: Re-read the vault and check the invariant held
+        ctx.accounts.vault.reload()?;
+        require!(
+            ctx.accounts.vault.amount >= required_balance,
+            CustomError::LoanNotRepaid
+        );
+
+        msg!("Flash loan of {} repaid with fee {}", amount, fee);
+        Ok(())
+    }
+}
+
+/// SECURE: Fee owed on a loan of `amount`, rounded up
+pub fn loan_fee(amount: u64) -> Result<u64> {
+    let product = (amount as u128)
+        .checked_mul(FLASH_LOAN_FEE_BPS as u128)
+        .ok_or(SecurityError::ArithmeticOverflow)?;
+    let fee = (product + BPS_DENOMINATOR as u128 - 1) / BPS_DENOMINATOR as u128;
+    Ok(u64::try_from(fee).map_err(|_| SecurityError::ArithmeticOverflow)?)
+}
+
+/// Invokes the receiver with `amount` and `fee` (little-endian u64s),
+/// forwarding `accounts` with the signer and writable flags they came with
+fn call_receiver<'info>(
+    receiver: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    amount: u64,
+    fee: u64,
+) -> Result<()> {
+    let metas = accounts
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: account.key(),
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        })
+        .collect();
+    let mut data = amount.to_le_bytes().to_vec();
+    data.extend_from_slice(&fee.to_le_bytes());
+
+    let mut infos = accounts.to_vec();
+    infos.push(receiver.clone());
+    // SECURE: Plain `invoke` - the pool_signer PDA signs nothing here
+    invoke(
+        &Instruction {
+            program_id: receiver.key(),
+            accounts: metas,
+            data,
+        },
+        &infos,
+    )?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct FlashLoanSecure<'info> {
+    #[account(has_one = vault @ CustomError::VaultMismatch)]
+    pub pool: Account<'info, LendingPool>,
+
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = vault.mint)]
+    pub borrower_token: Account<'info, TokenAccount>,
+
+    /// CONSTRAINT: Must be this pool's `[POOL_SIGNER_NAMESPACE, pool]` PDA
+    #[account(
+        seeds = [POOL_SIGNER_NAMESPACE.as_bytes(), pool.key().as_ref()],
+        bump = pool.signer_bump
+    )]
+    pub pool_signer: AccountInfo<'info>,
+
+    pub borrower: Signer<'info>,
+
+    /// The borrower's program, called with the loaned funds
+    #[account(executable)]
+    pub receiver_program: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+pub struct LendingPool {
+    pub vault: Pubkey,
+    pub signer_bump: u8, // Bump of the pool_signer PDA, the vault's authority
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Vault balance was not restored with the fee")]
+    LoanNotRepaid,
+
+    #[msg("Vault does not belong to this pool")]
+    VaultMismatch,
+}
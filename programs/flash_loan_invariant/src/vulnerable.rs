@@ -0,0 +1,170 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+};
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+// ============================================================================
+// VULNERABILITY: Unchecked Flash Loan Repayment
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// A flash loan lends tokens and expects them back, plus a fee, before the
+// instruction ends:
+//
+//     1. Transfer `amount` from the vault to the borrower
+//     2. Call the borrower's receiver program, which uses the funds and repays
+//     3. Make sure the vault got its money back
+//
+// This program does step 3 by asking the caller. `repaid` is an instruction
+// argument, so a receiver that keeps the tokens is paired with `repaid =
+// true` and the loan "succeeds". The vault is drained by `amount` per call.
+//
+// WHY IT'S UNSAFE:
+// - The only thing that proves repayment is the vault's balance, and it is
+//   never read
+// - Anything the caller supplies - flags, amounts, "receipts" - is a claim,
+//   not evidence
+// - Each call takes up to the whole vault with no collateral at all
+//
+// SEVERITY: CRITICAL
+// ============================================================================
+
+declare_id!("F1ashLoan1111111111111111111111111111111111");
+
+/// Fee charged on every loan, in basis points of the amount
+pub const FLASH_LOAN_FEE_BPS: u64 = 9;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// PDA namespace of the vault authority: `[POOL_SIGNER_NAMESPACE, pool]`
+pub const POOL_SIGNER_NAMESPACE: &str = "flash_loan_invariant/pool_signer";
+
+#[program]
+pub mod flash_loan_invariant {
+    use super::*;
+
+    /// VULNERABLE: Lend `amount` to the borrower's receiver program
+    ///
+    /// Accounts after the named ones are forwarded to the receiver.
+    pub fn flash_loan_vulnerable<'info>(
+        ctx: Context<'_, '_, '_, 'info, FlashLoanVulnerable<'info>>,
+        amount: u64,
+        repaid: bool,
+    ) -> Result<()> {
+        let fee = loan_fee(amount)?;
+        let pool_key = ctx.accounts.pool.key();
+        let signer_bump = [ctx.accounts.pool.signer_bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[POOL_SIGNER_NAMESPACE.as_bytes(), pool_key.as_ref(), &signer_bump]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.borrower_token.to_account_info(),
+                    authority: ctx.accounts.pool_signer.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        call_receiver(
+            &ctx.accounts.receiver_program,
+            ctx.remaining_accounts,
+            amount,
+            fee,
+        )?;
+
+        // VULNERABILITY: Takes the caller's word that the loan came back
+        require!(repaid, CustomError::LoanNotRepaid);
+
+        msg!("Flash loan of {} repaid with fee {}", amount, fee);
+        Ok(())
+    }
+}
+
+/// Fee owed on a loan of `amount`, rounded up
+pub fn loan_fee(amount: u64) -> Result<u64> {
+    let product = (amount as u128)
+        .checked_mul(FLASH_LOAN_FEE_BPS as u128)
+        .ok_or(CustomError::ArithmeticOverflow)?;
+    let fee = (product + BPS_DENOMINATOR as u128 - 1) / BPS_DENOMINATOR as u128;
+    Ok(u64::try_from(fee).map_err(|_| CustomError::ArithmeticOverflow)?)
+}
+
+/// Invokes the receiver with `amount` and `fee` (little-endian u64s),
+/// forwarding `accounts` with the signer and writable flags they came with
+fn call_receiver<'info>(
+    receiver: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    amount: u64,
+    fee: u64,
+) -> Result<()> {
+    let metas = accounts
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: account.key(),
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        })
+        .collect();
+    let mut data = amount.to_le_bytes().to_vec();
+    data.extend_from_slice(&fee.to_le_bytes());
+
+    let mut infos = accounts.to_vec();
+    infos.push(receiver.clone());
+    invoke(
+        &Instruction {
+            program_id: receiver.key(),
+            accounts: metas,
+            data,
+        },
+        &infos,
+    )?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct FlashLoanVulnerable<'info> {
+    #[account(has_one = vault)]
+    pub pool: Account<'info, LendingPool>,
+
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = vault.mint)]
+    pub borrower_token: Account<'info, TokenAccount>,
+
+    /// CONSTRAINT: Must be this pool's `[POOL_SIGNER_NAMESPACE, pool]` PDA
+    #[account(
+        seeds = [POOL_SIGNER_NAMESPACE.as_bytes(), pool.key().as_ref()],
+        bump = pool.signer_bump
+    )]
+    pub pool_signer: AccountInfo<'info>,
+
+    pub borrower: Signer<'info>,
+
+    /// The borrower's program, called with the loaned funds
+    #[account(executable)]
+    pub receiver_program: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+pub struct LendingPool {
+    pub vault: Pubkey,
+    pub signer_bump: u8, // Bump of the pool_signer PDA, the vault's authority
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Flash loan was not repaid")]
+    LoanNotRepaid,
+
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+}
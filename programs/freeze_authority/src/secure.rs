@@ -0,0 +1,163 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, FreezeAccount, Mint, ThawAccount, Token, TokenAccount};
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Gate the Freeze Authority on the Stored Admin
+// ============================================================================
+//
+// WHAT'S FIXED:
+// Freezing and thawing require the admin recorded in `Config` to sign, and
+// the program only signs as the `["freeze_authority"]` PDA after that:
+//
+//     has_one = admin @ Unauthorized     // admin: Signer
+//
+// The mint must be the one `Config` was set up for, and the token account
+// must hold it, so the PDA's signature can't be aimed at any other mint
+// that happens to name it as freeze authority.
+//
+// BEST PRACTICES:
+// 1. A PDA signature is the program's authority - gate every
+//    `invoke_signed` on who is asking
+// 2. Compare signers to the authority stored in state with `has_one`
+// 3. Pin the mint a PDA may act on, and tie token accounts to it
+// 4. Keep freeze and thaw behind the same check
+//
+// ============================================================================
+
+declare_id!("FreezeAuthority1111111111111111111111111111");
+
+/// PDA namespace of the singleton Config: `[CONFIG_NAMESPACE, program id]`
+pub const CONFIG_NAMESPACE: &str = "freeze_authority/config";
+
+/// PDA namespace of the mint's freeze authority:
+/// `[FREEZE_AUTHORITY_NAMESPACE, program id]`
+pub const FREEZE_AUTHORITY_NAMESPACE: &str = "freeze_authority/authority";
+
+#[program]
+pub mod freeze_authority_secure {
+    use super::*;
+
+    /// Create the `[CONFIG_NAMESPACE, program id]` PDA, with the signer as
+    /// admin of `mint`
+    pub fn initialize_config_safe(ctx: Context<InitializeConfigSafe>, mint: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.mint = mint;
+        config.bump = ctx.bumps.config;
+        config.freeze_bump = ctx.bumps.freeze_authority;
+        Ok(())
+    }
+
+    /// SECURE: Freeze `token_account`; admin only
+    pub fn freeze_account_safe(ctx: Context<FreezeSafe>) -> Result<()> {
+        // SECURE: The admin signed, checked by the account constraints
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            FREEZE_AUTHORITY_NAMESPACE.as_bytes(),
+            ID.as_ref(),
+            &[ctx.accounts.config.freeze_bump],
+        ]];
+        token::freeze_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            FreezeAccount {
+                account: ctx.accounts.token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.freeze_authority.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        msg!("Froze {}", ctx.accounts.token_account.key());
+        Ok(())
+    }
+
+    /// SECURE: Thaw `token_account`; admin only
+    pub fn thaw_account_safe(ctx: Context<FreezeSafe>) -> Result<()> {
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            FREEZE_AUTHORITY_NAMESPACE.as_bytes(),
+            ID.as_ref(),
+            &[ctx.accounts.config.freeze_bump],
+        ]];
+        token::thaw_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            ThawAccount {
+                account: ctx.accounts.token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.freeze_authority.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        msg!("Thawed {}", ctx.accounts.token_account.key());
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfigSafe<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Config::SPACE,
+        seeds = [CONFIG_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// PDA the mint names as its freeze authority
+    #[account(seeds = [FREEZE_AUTHORITY_NAMESPACE.as_bytes(), ID.as_ref()], bump)]
+    pub freeze_authority: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FreezeSafe<'info> {
+    /// CONSTRAINT: Only the stored admin may freeze or thaw
+    #[account(
+        seeds = [CONFIG_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump = config.bump,
+        has_one = admin @ SecurityError::Unauthorized,
+        has_one = mint @ CustomError::MintMismatch,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, token::mint = mint)]
+    pub token_account: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    /// PDA the mint names as its freeze authority
+    #[account(
+        seeds = [FREEZE_AUTHORITY_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump = config.freeze_bump
+    )]
+    pub freeze_authority: AccountInfo<'info>,
+
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey, // SECURE: The only signer who can freeze or thaw
+    pub mint: Pubkey,  // SECURE: The only mint the PDA acts on
+    pub bump: u8,
+    pub freeze_bump: u8, // Bump of the freeze_authority PDA
+}
+
+impl Config {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Mint is not the one this config manages")]
+    MintMismatch,
+}
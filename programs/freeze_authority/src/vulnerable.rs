@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, FreezeAccount, ThawAccount, Token};
+
+// ============================================================================
+// VULNERABILITY: Unguarded Freeze Authority
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// The vault's mint names the `["freeze_authority"]` PDA as its freeze
+// authority, so only this program can freeze or thaw its token accounts.
+// That power is meant for the admin stored in `Config` - to lock an account
+// during an incident, say.
+//
+// `freeze_account_vulnerable` signs with the PDA's seeds for whoever calls
+// it. It takes a `caller` signer and never compares it to `config.admin`:
+//
+//     1. Attacker calls `freeze_account_vulnerable` on a victim's account
+//     2. The program signs the Token program's FreezeAccount as the PDA
+//     3. The victim can't transfer, burn or close until someone thaws it
+//
+// WHY IT'S UNSAFE:
+// - The Token program trusts the PDA's signature; the PDA's seeds are the
+//   only thing standing between a caller and the freeze authority
+// - Seeds are public, so signing with them must be gated on who asked
+// - A stored admin that is never checked protects nothing
+//
+// SEVERITY: HIGH
+// ============================================================================
+
+declare_id!("FreezeAuthority1111111111111111111111111111");
+
+/// PDA namespace of the singleton Config: `[CONFIG_NAMESPACE, program id]`
+pub const CONFIG_NAMESPACE: &str = "freeze_authority/config";
+
+/// PDA namespace of the mint's freeze authority:
+/// `[FREEZE_AUTHORITY_NAMESPACE, program id]`
+pub const FREEZE_AUTHORITY_NAMESPACE: &str = "freeze_authority/authority";
+
+#[program]
+pub mod freeze_authority {
+    use super::*;
+
+    /// Create the `[CONFIG_NAMESPACE, program id]` PDA, with the signer as
+    /// admin of `mint`
+    pub fn initialize_config_vulnerable(
+        ctx: Context<InitializeConfigVulnerable>,
+        mint: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.mint = mint;
+        config.bump = ctx.bumps.config;
+        config.freeze_bump = ctx.bumps.freeze_authority;
+        Ok(())
+    }
+
+    /// VULNERABLE: Freeze `token_account`, for any caller
+    pub fn freeze_account_vulnerable(ctx: Context<FreezeVulnerable>) -> Result<()> {
+        // VULNERABILITY: Signs as the freeze authority without checking
+        // that `caller` is the admin
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            FREEZE_AUTHORITY_NAMESPACE.as_bytes(),
+            ID.as_ref(),
+            &[ctx.accounts.config.freeze_bump],
+        ]];
+        token::freeze_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            FreezeAccount {
+                account: ctx.accounts.token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.freeze_authority.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        msg!("Froze {}", ctx.accounts.token_account.key());
+        Ok(())
+    }
+
+    /// VULNERABLE: Thaw `token_account`, for any caller
+    pub fn thaw_account_vulnerable(ctx: Context<FreezeVulnerable>) -> Result<()> {
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            FREEZE_AUTHORITY_NAMESPACE.as_bytes(),
+            ID.as_ref(),
+            &[ctx.accounts.config.freeze_bump],
+        ]];
+        token::thaw_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            ThawAccount {
+                account: ctx.accounts.token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.freeze_authority.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        msg!("Thawed {}", ctx.accounts.token_account.key());
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfigVulnerable<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Config::SPACE,
+        seeds = [CONFIG_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// PDA the mint names as its freeze authority
+    #[account(seeds = [FREEZE_AUTHORITY_NAMESPACE.as_bytes(), ID.as_ref()], bump)]
+    pub freeze_authority: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FreezeVulnerable<'info> {
+    #[account(seeds = [CONFIG_NAMESPACE.as_bytes(), ID.as_ref()], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub token_account: AccountInfo<'info>,
+
+    pub mint: AccountInfo<'info>,
+
+    /// PDA the mint names as its freeze authority
+    #[account(
+        seeds = [FREEZE_AUTHORITY_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump = config.freeze_bump
+    )]
+    pub freeze_authority: AccountInfo<'info>,
+
+    // VULNERABILITY: Any signer at all - never checked against config.admin
+    pub caller: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey, // Meant to be the only one who can freeze or thaw
+    pub mint: Pubkey,
+    pub bump: u8,
+    pub freeze_bump: u8, // Bump of the freeze_authority PDA
+}
+
+impl Config {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
@@ -9,19 +9,35 @@ use anchor_lang::prelude::*;
 // - Authority is marked as a signer (prevents unauthorized claims)
 // - Explicit validation that authority matches the stored owner
 // - Multiple layers of validation for sensitive operations
+// - Ownership changes hands in two steps: the owner proposes, and the new
+//   owner accepts in a separate transaction, so a typo'd address never
+//   takes control
 //
 // BEST PRACTICES:
 // 1. Always mark authorities as #[account(signer)]
 // 2. Explicitly compare authority.key() == stored_owner
 // 3. Use constraints to codify permission rules
 // 4. Fail fast if authority is wrong (require! macro)
+// 5. Transfer ownership by propose + accept, never in a single step
 //
 // ============================================================================
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    ed25519_program,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use security_utils::{pda, SecurityError};
 
 declare_id!("22222222222222222222222222222222");
 
+/// Most delegates one account can register
+pub const MAX_DELEGATES: usize = 4;
+
+/// PDA namespace of a delegate registry: `[DELEGATES_NAMESPACE, user_account]`
+pub const DELEGATES_NAMESPACE: &str = "incorrect_authority/delegates";
+
 #[program]
 pub mod incorrect_authority_check_secure {
     use super::*;
@@ -35,35 +51,364 @@ pub mod incorrect_authority_check_secure {
         
         // Only the signer (authority) can initialize their own account
         // This is enforced by Anchor via the #[account(signer)] constraint
+
+        // SECURE: Never bind an account to an owner nobody can sign for
+        validate_owner(&ctx.accounts.authority.key())?;
         
         account.owner = ctx.accounts.authority.key();
         account.balance = initial_amount;
+        account.pending_owner = None;
 
         msg!("Account initialized with owner: {}", account.owner);
         Ok(())
     }
 
     /// SECURE: Withdraw with explicit authority validation
+    ///
+    /// Returns a `WithdrawReceipt`, which Anchor sets as the instruction's
+    /// return data for clients and CPI callers.
     pub fn withdraw_safe(
         ctx: Context<WithdrawSafe>,
         amount: u64,
-    ) -> Result<()> {
+    ) -> Result<WithdrawReceipt> {
         let account = &mut ctx.accounts.user_account;
 
         // EXPLICIT VALIDATION: Verify the signer IS the owner
         require_eq!(
             ctx.accounts.authority.key(),
             account.owner,
-            CustomError::Unauthorized
+            SecurityError::Unauthorized
         );
 
         require!(account.balance >= amount, CustomError::InsufficientFunds);
         
         account.balance -= amount;
+
+        emit!(WithdrawEvent {
+            user: account.owner,
+            amount,
+            new_total: account.balance,
+        });
         
         msg!("Withdrew {} SOL", amount);
+        Ok(WithdrawReceipt {
+            new_balance: account.balance,
+            withdrawn: amount,
+        })
+    }
+
+    /// SECURE: Withdraw with every check expressed as an account constraint
+    ///
+    /// Same effect as `withdraw_safe`, but ownership and funds are checked
+    /// in `WithdrawConstrainedSafe` before the handler runs, so the body
+    /// can't forget one of them.
+    pub fn withdraw_constrained_safe(
+        ctx: Context<WithdrawConstrainedSafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.user_account;
+        account.balance -= amount;
+
+        msg!("Withdrew {} SOL", amount);
+        Ok(())
+    }
+
+    /// SECURE: Create the (empty) delegate registry for a user account
+    pub fn init_delegates_safe(
+        ctx: Context<InitDelegatesSafe>,
+    ) -> Result<()> {
+        let delegates = &mut ctx.accounts.delegates;
+        delegates.user_account = ctx.accounts.user_account.key();
+        delegates.entries = Vec::new();
+        delegates.bump = ctx.bumps.delegates;
+        Ok(())
+    }
+
+    /// SECURE: Allow `delegate` to withdraw up to `limit` in total
+    ///
+    /// Registering an existing delegate again replaces their limit; what
+    /// they already withdrew still counts against it.
+    pub fn register_delegate_safe(
+        ctx: Context<RegisterDelegateSafe>,
+        delegate: Pubkey,
+        limit: u64,
+    ) -> Result<()> {
+        let entries = &mut ctx.accounts.delegates.entries;
+
+        match entries.iter_mut().find(|entry| entry.delegate == delegate) {
+            Some(entry) => entry.limit = limit,
+            None => {
+                require!(entries.len() < MAX_DELEGATES, CustomError::TooManyDelegates);
+                entries.push(DelegateEntry {
+                    delegate,
+                    limit,
+                    withdrawn: 0,
+                });
+            }
+        }
+
+        msg!("Delegate {} may withdraw up to {}", delegate, limit);
+        Ok(())
+    }
+
+    /// SECURE: Withdraw on the owner's behalf, within the delegate's limit
+    pub fn withdraw_as_delegate_safe(
+        ctx: Context<WithdrawAsDelegateSafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.user_account;
+        let signer = ctx.accounts.delegate.key();
+
+        // SECURE: Only delegates the owner registered
+        let entry = ctx
+            .accounts
+            .delegates
+            .entries
+            .iter_mut()
+            .find(|entry| entry.delegate == signer)
+            .ok_or(CustomError::NotADelegate)?;
+
+        // SECURE: The limit is cumulative, and the running total can't wrap
+        // back under it
+        let withdrawn = entry
+            .withdrawn
+            .checked_add(amount)
+            .ok_or(CustomError::DelegateLimitExceeded)?;
+        require!(withdrawn <= entry.limit, CustomError::DelegateLimitExceeded);
+        require!(account.balance >= amount, CustomError::InsufficientFunds);
+
+        entry.withdrawn = withdrawn;
+        account.balance -= amount;
+
+        msg!("Delegate {} withdrew {} SOL", signer, amount);
+        Ok(())
+    }
+
+    /// SECURE: Withdraw real lamports to a recipient wallet
+    pub fn withdraw_sol_safe(
+        ctx: Context<WithdrawSolSafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.user_account;
+
+        // EXPLICIT VALIDATION: Verify the signer IS the owner
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            account.owner,
+            SecurityError::Unauthorized
+        );
+        // (The recipient's owner is checked by the account constraint)
+
+        require!(account.balance >= amount, CustomError::InsufficientFunds);
+        account.balance -= amount;
+
+        // Program-owned account, so this program may debit it directly
+        account.sub_lamports(amount)?;
+        ctx.accounts.recipient.add_lamports(amount)?;
+
+        msg!("Withdrew {} lamports to {}", amount, ctx.accounts.recipient.key());
         Ok(())
     }
+
+    /// SECURE: Withdraw using an authorization the owner signed off-chain
+    ///
+    /// The transaction must contain an Ed25519 program instruction right
+    /// before this one, verifying the owner's signature over
+    /// `authorization_message(user_account, amount, nonce)`. Anyone may
+    /// submit the transaction (e.g. a relayer); only the owner can sign.
+    pub fn withdraw_with_authorization_safe(
+        ctx: Context<WithdrawWithAuthorizationSafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.user_account;
+        let instructions = &ctx.accounts.instructions;
+
+        // SECURE: Find the instruction immediately before this one
+        let current_index = load_current_index_checked(instructions)?;
+        require!(current_index > 0, CustomError::InvalidAuthorizationSignature);
+        let signature_ix =
+            load_instruction_at_checked((current_index - 1) as usize, instructions)?;
+
+        // SECURE: It must be the Ed25519 precompile (which has already
+        // verified the signature itself, or the transaction would have failed)
+        require_keys_eq!(
+            signature_ix.program_id,
+            ed25519_program::ID,
+            CustomError::InvalidAuthorizationSignature
+        );
+
+        // SECURE: ...and it must have verified the OWNER's signature over
+        // exactly this withdrawal, not some other key or message
+        let expected_message = authorization_message(&account.key(), amount, account.nonce);
+        verify_ed25519_instruction(&signature_ix.data, &account.owner, &expected_message)?;
+
+        require!(account.balance >= amount, CustomError::InsufficientFunds);
+
+        // SECURE: Consume the nonce so the same authorization can't be replayed
+        account.nonce = account
+            .nonce
+            .checked_add(1)
+            .ok_or(CustomError::InvalidAuthorizationSignature)?;
+        account.balance -= amount;
+
+        msg!("Withdrew {} SOL with owner authorization", amount);
+        Ok(())
+    }
+
+    /// SECURE: Rotate mint authority, only callable by the current authority
+    ///
+    /// `authority_seeds` must be given (bump included) when the new authority
+    /// is a PDA of this program, proving the program can sign for it.
+    pub fn rotate_mint_authority_safe(
+        ctx: Context<RotateMintAuthoritySafe>,
+        new_authority: Pubkey,
+        authority_seeds: Option<Vec<Vec<u8>>>,
+    ) -> Result<()> {
+        let mint_info = &mut ctx.accounts.mint_info;
+
+        // The signer check and the `has_one = mint_authority` constraint
+        // guarantee only the current authority reaches this point.
+
+        // SECURE: Reject the default pubkey - nobody holds its private key,
+        // so rotating to it would permanently disable minting
+        require_keys_neq!(
+            new_authority,
+            Pubkey::default(),
+            CustomError::InvalidNewAuthority
+        );
+
+        // SECURE: Don't hand authority to an address that can't sign for itself
+        validate_new_authority(&new_authority, authority_seeds.as_deref())?;
+
+        mint_info.mint_authority = new_authority;
+
+        msg!("Mint authority rotated to: {}", new_authority);
+        Ok(())
+    }
+
+    /// SECURE: Step 1 of an ownership transfer, callable by the owner
+    ///
+    /// Nothing changes hands yet: `new_owner` must call `accept_ownership`.
+    /// Until then the owner keeps full control and can propose again to
+    /// correct a mistake, or propose themselves to cancel.
+    pub fn propose_new_owner(ctx: Context<ProposeNewOwner>, new_owner: Pubkey) -> Result<()> {
+        // SECURE: Never propose an owner nobody can sign for
+        validate_owner(&new_owner)?;
+
+        ctx.accounts.user_account.pending_owner = Some(new_owner);
+
+        msg!("Proposed new owner: {}", new_owner);
+        Ok(())
+    }
+
+    /// SECURE: Step 2 of an ownership transfer, callable by the proposed owner
+    pub fn accept_ownership(ctx: Context<AcceptOwnership>) -> Result<()> {
+        let account = &mut ctx.accounts.user_account;
+
+        // The `pending_owner` constraint guarantees the signer was proposed;
+        // signing proves they hold the key, so the address can't be a typo
+        account.owner = ctx.accounts.new_owner.key();
+        account.pending_owner = None;
+
+        msg!("Ownership accepted by: {}", account.owner);
+        Ok(())
+    }
+}
+
+/// Rejects the default (all-zero) pubkey as an account owner.
+/// Funds owned by it are unspendable since nobody holds its private key.
+pub fn validate_owner(owner: &Pubkey) -> Result<()> {
+    require_keys_neq!(*owner, Pubkey::default(), CustomError::InvalidOwner);
+    Ok(())
+}
+
+/// Rejects an authority that is a PDA (off the ed25519 curve) unless
+/// `seeds` are given and derive it under this program.
+///
+/// A PDA has no private key: only the program it was derived from can sign
+/// for it, and only by knowing the seeds. Without them, authority would
+/// either be lost for good or end up behind whatever code path in this
+/// program happens to sign with that PDA - a confused deputy.
+pub fn validate_new_authority(authority: &Pubkey, seeds: Option<&[Vec<u8>]>) -> Result<()> {
+    match seeds {
+        Some(seeds) => {
+            let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+            let derived = Pubkey::create_program_address(&seeds, &ID)
+                .map_err(|_| CustomError::AuthorityCannotBePda)?;
+            require_keys_eq!(derived, *authority, CustomError::AuthorityCannotBePda);
+        }
+        None => {
+            // Pubkey::is_on_curve is not available on-chain, so decompress directly
+            let on_curve = CompressedEdwardsY::from_slice(authority.as_ref())
+                .decompress()
+                .is_some();
+            require!(on_curve, CustomError::AuthorityCannotBePda);
+        }
+    }
+    Ok(())
+}
+
+/// The exact bytes the owner signs to authorize a withdrawal
+pub fn authorization_message(user_account: &Pubkey, amount: u64, nonce: u64) -> Vec<u8> {
+    let mut message = b"withdraw".to_vec();
+    message.extend_from_slice(user_account.as_ref());
+    message.extend_from_slice(&amount.to_le_bytes());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message
+}
+
+// Layout of an Ed25519 program instruction with a single signature:
+// [num_signatures: u8, padding: u8, offsets: 7 x u16, ...payload]
+const ED25519_OFFSETS_START: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+
+/// Checks an Ed25519 program instruction verified `expected_signer`
+/// signing `expected_message`, with all data inside that same instruction.
+fn verify_ed25519_instruction(
+    data: &[u8],
+    expected_signer: &Pubkey,
+    expected_message: &[u8],
+) -> Result<()> {
+    let read_u16 = |offset: usize| -> Result<u16> {
+        data.get(offset..offset + 2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .ok_or_else(|| CustomError::InvalidAuthorizationSignature.into())
+    };
+
+    require!(
+        data.len() >= ED25519_OFFSETS_START + ED25519_OFFSETS_LEN && data[0] == 1,
+        CustomError::InvalidAuthorizationSignature
+    );
+
+    let offsets = ED25519_OFFSETS_START;
+    let signature_ix_index = read_u16(offsets + 2)?;
+    let public_key_offset = read_u16(offsets + 4)? as usize;
+    let public_key_ix_index = read_u16(offsets + 6)?;
+    let message_offset = read_u16(offsets + 8)? as usize;
+    let message_size = read_u16(offsets + 10)? as usize;
+    let message_ix_index = read_u16(offsets + 12)?;
+
+    // SECURE: u16::MAX means "this instruction". Anything else would let the
+    // signature, key or message be pulled from an attacker-chosen instruction.
+    require!(
+        signature_ix_index == u16::MAX
+            && public_key_ix_index == u16::MAX
+            && message_ix_index == u16::MAX,
+        CustomError::InvalidAuthorizationSignature
+    );
+
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(CustomError::InvalidAuthorizationSignature)?;
+    let message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(CustomError::InvalidAuthorizationSignature)?;
+
+    require!(
+        public_key == expected_signer.as_ref() && message == expected_message,
+        CustomError::InvalidAuthorizationSignature
+    );
+    Ok(())
 }
 
 #[derive(Accounts)]
@@ -72,7 +417,7 @@ pub struct InitializeSafe<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 8, // discriminator + owner + balance
+        space = UserAccount::SPACE,
     )]
     pub user_account: Account<'info, UserAccount>,
     
@@ -95,17 +440,232 @@ pub struct WithdrawSafe<'info> {
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+#[instruction(amount: u64)]
+pub struct WithdrawConstrainedSafe<'info> {
+    /// CONSTRAINT: Owned by the signer below, and holds at least `amount`
+    #[account(
+        mut,
+        has_one = owner @ SecurityError::Unauthorized,
+        constraint = user_account.balance >= amount @ CustomError::InsufficientFunds,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitDelegatesSafe<'info> {
+    /// CONSTRAINT: Only the owner may open a registry for their account
+    #[account(has_one = owner @ SecurityError::Unauthorized)]
+    pub user_account: Account<'info, UserAccount>,
+
+    /// One registry per user account, at `[DELEGATES_NAMESPACE, user_account]`
+    #[account(
+        init,
+        payer = owner,
+        space = Delegates::SPACE,
+        seeds = [DELEGATES_NAMESPACE.as_bytes(), user_account.key().as_ref()],
+        bump,
+    )]
+    pub delegates: Account<'info, Delegates>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterDelegateSafe<'info> {
+    /// CONSTRAINT: Only the owner may grant withdrawal rights
+    #[account(has_one = owner @ SecurityError::Unauthorized)]
+    pub user_account: Account<'info, UserAccount>,
+
+    #[account(
+        mut,
+        seeds = [DELEGATES_NAMESPACE.as_bytes(), user_account.key().as_ref()],
+        bump = delegates.bump,
+    )]
+    pub delegates: Account<'info, Delegates>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawAsDelegateSafe<'info> {
+    #[account(mut)]
+    pub user_account: Account<'info, UserAccount>,
+
+    /// CONSTRAINT: Must be this user account's registry
+    #[account(
+        mut,
+        seeds = [DELEGATES_NAMESPACE.as_bytes(), user_account.key().as_ref()],
+        bump = delegates.bump,
+    )]
+    pub delegates: Account<'info, Delegates>,
+
+    /// SECURE: Must sign AND be registered in `delegates`
+    pub delegate: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSolSafe<'info> {
+    /// Holds the lamports being withdrawn
+    #[account(mut)]
+    pub user_account: Account<'info, UserAccount>,
+
+    #[account(signer)]
+    pub authority: Signer<'info>,
+
+    /// SECURE: Must be a plain system account (a wallet). Lamports sent to
+    /// an account owned by another program can only be moved by that
+    /// program, and executable accounts can't be credited at all.
+    #[account(
+        mut,
+        owner = anchor_lang::system_program::ID @ CustomError::RecipientNotSystemOwned,
+    )]
+    pub recipient: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawWithAuthorizationSafe<'info> {
+    /// The account being withdrawn from; its owner must have signed off-chain
+    #[account(mut)]
+    pub user_account: Account<'info, UserAccount>,
+
+    /// SECURE: Address constraint guarantees this is the real instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RotateMintAuthoritySafe<'info> {
+    /// The mint whose authority is being rotated
+    /// CONSTRAINT: The stored authority must be the signer below
+    #[account(
+        mut,
+        has_one = mint_authority @ SecurityError::Unauthorized,
+    )]
+    pub mint_info: Account<'info, MintInfo>,
+
+    /// SECURE: The current authority must sign the rotation
+    pub mint_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeNewOwner<'info> {
+    /// CONSTRAINT: Only the current owner may propose a successor
+    #[account(mut, has_one = owner @ SecurityError::Unauthorized)]
+    pub user_account: Account<'info, UserAccount>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOwnership<'info> {
+    /// CONSTRAINT: Only the proposed owner may accept
+    #[account(
+        mut,
+        constraint = user_account.pending_owner == Some(new_owner.key())
+            @ CustomError::NotPendingOwner,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+
+    /// SECURE: Must sign, proving the proposed address is a key someone holds
+    pub new_owner: Signer<'info>,
+}
+
 #[account]
+#[derive(InitSpace)]
 pub struct UserAccount {
     pub owner: Pubkey,
     pub balance: u64,
+    pub nonce: u64, // SECURE: Incremented per off-chain authorization used
+    pub pending_owner: Option<Pubkey>, // SECURE: Proposed, but not yet accepted
+}
+
+impl UserAccount {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Who may withdraw from `user_account` besides its owner, and how much
+#[account]
+#[derive(InitSpace)]
+pub struct Delegates {
+    pub user_account: Pubkey,
+    #[max_len(MAX_DELEGATES)]
+    pub entries: Vec<DelegateEntry>, // At most MAX_DELEGATES
+    pub bump: u8,
+}
+
+impl Delegates {
+    /// Bytes to allocate: discriminator + fields, at MAX_DELEGATES entries
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+
+    /// Address and bump of `user_account`'s registry
+    pub fn address(user_account: &Pubkey) -> Result<(Pubkey, u8)> {
+        pda::derive(DELEGATES_NAMESPACE, user_account, &ID)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct DelegateEntry {
+    pub delegate: Pubkey,
+    pub limit: u64,     // Total this delegate may ever withdraw
+    pub withdrawn: u64, // Total withdrawn so far
+}
+
+#[account]
+pub struct MintInfo {
+    pub mint: Pubkey,
+    pub mint_authority: Pubkey,
+}
+
+#[event]
+pub struct WithdrawEvent {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub new_total: u64, // The account's balance afterwards
+}
+
+/// Result of `withdraw_safe`, returned as Borsh-encoded return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WithdrawReceipt {
+    pub new_balance: u64, // The account's balance after the withdrawal
+    pub withdrawn: u64,
 }
 
 #[error_code]
 pub enum CustomError {
-    #[msg("Unauthorized: authority does not match owner")]
-    Unauthorized,
-    
     #[msg("Insufficient funds for withdrawal")]
     InsufficientFunds,
+
+    #[msg("New authority must not be the default pubkey")]
+    InvalidNewAuthority,
+
+    #[msg("Owner must not be the default pubkey")]
+    InvalidOwner,
+
+    #[msg("Withdrawal authorization signature is missing or invalid")]
+    InvalidAuthorizationSignature,
+
+    #[msg("Authority is a PDA and no matching signer seeds were provided")]
+    AuthorityCannotBePda,
+
+    #[msg("Withdrawal recipient must be a system-owned account")]
+    RecipientNotSystemOwned,
+
+    #[msg("Signer is not a registered delegate for this account")]
+    NotADelegate,
+
+    #[msg("Withdrawal would exceed the delegate's limit")]
+    DelegateLimitExceeded,
+
+    #[msg("Delegate registry is full")]
+    TooManyDelegates,
+
+    #[msg("Signer is not the proposed new owner")]
+    NotPendingOwner,
 }
@@ -35,6 +35,8 @@ pub mod incorrect_authority_check {
         // VULNERABILITY: We don't verify who is initializing this!
         // We just set an owner, but never check if the caller is authorized
         // Anyone can initialize and claim ownership of accounts
+        // VULNERABILITY: The owner can even be Pubkey::default(),
+        // locking the balance under a key nobody controls
         
         account.owner = ctx.accounts.authority.key();
         account.balance = initial_amount;
@@ -62,6 +64,103 @@ pub mod incorrect_authority_check {
         msg!("Withdrew {} SOL", amount);
         Ok(())
     }
+
+    /// VULNERABLE: Withdraw "on the owner's behalf"
+    pub fn withdraw_as_delegate_unsafe(
+        ctx: Context<WithdrawAsDelegateUnsafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.user_account;
+
+        // VULNERABILITY: There is no registry of delegates and no limit -
+        // any signer at all is treated as one and can drain the balance
+        require!(account.balance >= amount, CustomError::InsufficientFunds);
+        account.balance -= amount;
+
+        msg!("Delegate {} withdrew {} SOL", ctx.accounts.delegate.key(), amount);
+        Ok(())
+    }
+
+    /// VULNERABLE: Withdraw with the checks written out inline
+    pub fn withdraw_inline_unsafe(
+        ctx: Context<WithdrawInlineUnsafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.user_account;
+
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            account.owner,
+            CustomError::Unauthorized
+        );
+
+        // VULNERABILITY: The balance check was forgotten. Nothing in the
+        // accounts struct catches it either, and saturating_sub hides the
+        // shortfall: asking for more than the balance "withdraws" it all.
+        account.balance = account.balance.saturating_sub(amount);
+
+        msg!("Withdrew {} SOL", amount);
+        Ok(())
+    }
+
+    /// VULNERABLE: Withdraw real lamports to any recipient
+    pub fn withdraw_sol_unsafe(
+        ctx: Context<WithdrawSolUnsafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.user_account;
+
+        require!(account.balance >= amount, CustomError::InsufficientFunds);
+        account.balance -= amount;
+
+        // VULNERABILITY: `recipient` can be any account. If another program
+        // owns it, the lamports are now under that program's control - and
+        // unless it has a way to release them, they are trapped for good.
+        account.sub_lamports(amount)?;
+        ctx.accounts.recipient.add_lamports(amount)?;
+
+        msg!("Withdrew {} lamports to {}", amount, ctx.accounts.recipient.key());
+        Ok(())
+    }
+
+    /// VULNERABLE: Withdraw using an "off-chain authorization" nobody verifies
+    pub fn withdraw_with_authorization_unsafe(
+        ctx: Context<WithdrawWithAuthorizationUnsafe>,
+        amount: u64,
+        signature: [u8; 64],
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.user_account;
+
+        // VULNERABILITY: We only check that *some* signature bytes were sent.
+        // They are never verified against the owner's key or the message,
+        // so any 64 non-zero bytes "authorize" the withdrawal.
+        // VULNERABILITY: No nonce either - a real signature could be replayed
+        require!(signature != [0u8; 64], CustomError::InsufficientFunds);
+
+        require!(account.balance >= amount, CustomError::InsufficientFunds);
+        account.balance -= amount;
+
+        msg!("Withdrew {} SOL with owner authorization", amount);
+        Ok(())
+    }
+
+    /// VULNERABLE: Rotate mint authority without checking the caller
+    pub fn rotate_mint_authority_unsafe(
+        ctx: Context<RotateMintAuthorityUnsafe>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let mint_info = &mut ctx.accounts.mint_info;
+
+        // VULNERABILITY: Nobody checks that `current_authority` signed, or that
+        // it is even the stored mint authority. Anyone can take over minting.
+        //
+        // VULNERABILITY: `new_authority` is not validated either. Setting it to
+        // Pubkey::default() leaves the mint with an authority nobody controls.
+        mint_info.mint_authority = new_authority;
+
+        msg!("Mint authority rotated to: {}", new_authority);
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
@@ -88,14 +187,70 @@ pub struct WithdrawUnsafe<'info> {
     pub authority: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct WithdrawAsDelegateUnsafe<'info> {
+    #[account(mut)]
+    pub user_account: Account<'info, UserAccount>,
+
+    /// VULNERABILITY: A signer, but never checked against anything
+    pub delegate: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawInlineUnsafe<'info> {
+    /// VULNERABILITY: No constraints - the handler has to remember them all
+    #[account(mut)]
+    pub user_account: Account<'info, UserAccount>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSolUnsafe<'info> {
+    #[account(mut, has_one = owner)]
+    pub user_account: Account<'info, UserAccount>,
+
+    pub owner: Signer<'info>,
+
+    /// VULNERABILITY: Owner never checked - programs and PDAs accepted
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawWithAuthorizationUnsafe<'info> {
+    /// VULNERABILITY: Nothing ties the withdrawal to the owner
+    #[account(mut)]
+    pub user_account: Account<'info, UserAccount>,
+}
+
+#[derive(Accounts)]
+pub struct RotateMintAuthorityUnsafe<'info> {
+    /// The mint whose authority is being rotated
+    #[account(mut)]
+    pub mint_info: Account<'info, MintInfo>,
+
+    /// VULNERABILITY: Not a signer and never compared to the stored authority
+    pub current_authority: AccountInfo<'info>,
+}
+
 #[account]
 pub struct UserAccount {
     pub owner: Pubkey,
     pub balance: u64,
 }
 
+#[account]
+pub struct MintInfo {
+    pub mint: Pubkey,
+    pub mint_authority: Pubkey,
+}
+
 #[error_code]
 pub enum CustomError {
     #[msg("Insufficient funds")]
     InsufficientFunds,
+
+    #[msg("Unauthorized")]
+    Unauthorized,
 }
@@ -0,0 +1,179 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::{hash, hashv};
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Commit to a Secret Before Anyone Enters
+// ============================================================================
+//
+// WHAT'S FIXED:
+// The authority picks a random 32 byte secret off-chain and stores only its
+// hash when the lottery is created. Entries close when the authority
+// reveals the secret, which must match the commitment:
+//
+//     require!(hash(secret) == commitment, InvalidReveal)
+//     index = hash(secret || entrants) % entrants.len()
+//
+// No entrant knows the secret, so none can predict the winner - and the
+// slot the draw lands in no longer matters. The authority can't pick a
+// different secret after seeing the entrants, because the commitment was
+// fixed before the first entry.
+//
+// Commit-reveal still trusts the authority not to enter themselves, or to
+// withhold a reveal they dislike. When that trust isn't acceptable, read
+// the result from a verifiable random function (VRF) account instead -
+// an oracle's randomness comes with a proof the program checks, and
+// nobody, authority included, learns it before the request is made.
+//
+// BEST PRACTICES:
+// 1. Never derive randomness from `Clock`, `SlotHashes` or blockhashes
+// 2. Commit to the secret before the inputs it decides over are known
+// 3. Verify the reveal against the commitment on-chain
+// 4. Use a VRF when no single party may know the outcome in advance
+//
+// ============================================================================
+
+declare_id!("Randomness111111111111111111111111111111111");
+
+/// Most entrants one lottery accepts
+pub const MAX_ENTRANTS: usize = 16;
+
+/// PDA namespace of a Lottery: `[LOTTERY_NAMESPACE, authority]`
+pub const LOTTERY_NAMESPACE: &str = "insecure_randomness/lottery";
+
+#[program]
+pub mod insecure_randomness_secure {
+    use super::*;
+
+    /// Create the `[LOTTERY_NAMESPACE, authority]` PDA, committed to `hash(secret)`
+    pub fn initialize_lottery_safe(
+        ctx: Context<InitializeLotterySafe>,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        lottery.authority = ctx.accounts.authority.key();
+        lottery.commitment = commitment;
+        lottery.bump = ctx.bumps.lottery;
+        Ok(())
+    }
+
+    /// Add the signer to the lottery
+    pub fn enter_safe(ctx: Context<EnterSafe>) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        require!(lottery.winner.is_none(), CustomError::AlreadyDrawn);
+        require!(
+            lottery.entrants.len() < MAX_ENTRANTS,
+            CustomError::LotteryFull
+        );
+
+        lottery.entrants.push(ctx.accounts.entrant.key());
+        Ok(())
+    }
+
+    /// SECURE: Reveal the committed secret and pick the winner from it
+    pub fn draw_winner_safe(ctx: Context<DrawWinnerSafe>, secret: [u8; 32]) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        require!(lottery.winner.is_none(), CustomError::AlreadyDrawn);
+        require!(!lottery.entrants.is_empty(), CustomError::NoEntrants);
+
+        // SECURE: The secret was fixed before anyone entered
+        require!(
+            hash(&secret).to_bytes() == lottery.commitment,
+            CustomError::InvalidReveal
+        );
+
+        let winner = lottery.entrants[draw_index(&secret, &lottery.entrants)];
+        lottery.winner = Some(winner);
+
+        msg!("Winner: {}", winner);
+        Ok(())
+    }
+}
+
+/// Index of the entrant `secret` picks, out of `entrants`
+///
+/// Mixing in the entrants ties the result to this lottery's field; without
+/// `secret` the hash can't be computed, so entrants can't predict it.
+pub fn draw_index(secret: &[u8; 32], entrants: &[Pubkey]) -> usize {
+    let mut inputs: Vec<&[u8]> = vec![secret];
+    inputs.extend(entrants.iter().map(|entrant| entrant.as_ref()));
+    let hash = hashv(&inputs).to_bytes();
+
+    let mut seed = [0u8; 8];
+    seed.copy_from_slice(&hash[..8]);
+    (u64::from_le_bytes(seed) % entrants.len() as u64) as usize
+}
+
+#[derive(Accounts)]
+pub struct InitializeLotterySafe<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Lottery::SPACE,
+        seeds = [LOTTERY_NAMESPACE.as_bytes(), authority.key().as_ref()],
+        bump,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnterSafe<'info> {
+    #[account(
+        mut,
+        seeds = [LOTTERY_NAMESPACE.as_bytes(), lottery.authority.as_ref()],
+        bump = lottery.bump
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub entrant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DrawWinnerSafe<'info> {
+    /// CONSTRAINT: Only the authority who committed can reveal
+    #[account(
+        mut,
+        seeds = [LOTTERY_NAMESPACE.as_bytes(), lottery.authority.as_ref()],
+        bump = lottery.bump,
+        has_one = authority @ SecurityError::Unauthorized,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Lottery {
+    pub authority: Pubkey,
+    pub commitment: [u8; 32], // hash(secret), set before any entries
+    #[max_len(MAX_ENTRANTS)]
+    pub entrants: Vec<Pubkey>,
+    pub winner: Option<Pubkey>,
+    pub bump: u8,
+}
+
+impl Lottery {
+    /// Bytes to allocate: discriminator + fields, at MAX_ENTRANTS entrants
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Lottery already has a winner")]
+    AlreadyDrawn,
+
+    #[msg("Lottery is full")]
+    LotteryFull,
+
+    #[msg("Lottery has no entrants")]
+    NoEntrants,
+
+    #[msg("Secret does not match the lottery's commitment")]
+    InvalidReveal,
+}
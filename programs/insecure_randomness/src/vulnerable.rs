@@ -0,0 +1,159 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+// ============================================================================
+// VULNERABILITY: Randomness From the Clock
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// The lottery picks its winner from the slot the draw lands in:
+//
+//     index = hash(slot) % entrants.len()
+//
+// Hashing makes the result look random, but nothing secret goes into it.
+// The slot is public before the transaction runs, so anyone can compute
+// the winner ahead of time - and the draw is open to anyone:
+//
+//     1. Attacker enters the lottery
+//     2. Before each slot, runs `draw_index` off-chain (or reads `Clock`
+//        in their own program, in the same transaction as the draw)
+//     3. Only sends the draw in a slot that picks them
+//
+// WHY IT'S UNSAFE:
+// - `Clock`, `SlotHashes` and recent blockhashes are known to everyone
+//   before the instruction runs
+// - A leader chooses which slot a transaction lands in, and can skip it
+// - A program can check the outcome and abort the transaction if it loses
+//
+// SEVERITY: HIGH
+// ============================================================================
+
+declare_id!("Randomness111111111111111111111111111111111");
+
+/// Most entrants one lottery accepts
+pub const MAX_ENTRANTS: usize = 16;
+
+/// PDA namespace of a Lottery: `[LOTTERY_NAMESPACE, authority]`
+pub const LOTTERY_NAMESPACE: &str = "insecure_randomness/lottery";
+
+#[program]
+pub mod insecure_randomness {
+    use super::*;
+
+    /// Create the `[LOTTERY_NAMESPACE, authority]` PDA with no entrants
+    pub fn initialize_lottery_vulnerable(ctx: Context<InitializeLotteryVulnerable>) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        lottery.authority = ctx.accounts.authority.key();
+        lottery.bump = ctx.bumps.lottery;
+        Ok(())
+    }
+
+    /// Add the signer to the lottery
+    pub fn enter_vulnerable(ctx: Context<EnterVulnerable>) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        require!(lottery.winner.is_none(), CustomError::AlreadyDrawn);
+        require!(
+            lottery.entrants.len() < MAX_ENTRANTS,
+            CustomError::LotteryFull
+        );
+
+        lottery.entrants.push(ctx.accounts.entrant.key());
+        Ok(())
+    }
+
+    /// VULNERABLE: Pick the winner from the current slot
+    pub fn draw_winner_vulnerable(ctx: Context<DrawWinnerVulnerable>) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        require!(lottery.winner.is_none(), CustomError::AlreadyDrawn);
+        require!(!lottery.entrants.is_empty(), CustomError::NoEntrants);
+
+        // VULNERABILITY: The slot is public - whoever sends this already
+        // knows who it picks
+        let slot = Clock::get()?.slot;
+        let winner = lottery.entrants[draw_index(slot, lottery.entrants.len())];
+        lottery.winner = Some(winner);
+
+        msg!("Winner: {}", winner);
+        Ok(())
+    }
+}
+
+/// Index of the entrant a draw in `slot` picks, out of `entrants`
+///
+/// Public on purpose: anyone can run the program's own "randomness" ahead
+/// of time.
+pub fn draw_index(slot: u64, entrants: usize) -> usize {
+    let hash = hashv(&[&slot.to_le_bytes()]).to_bytes();
+    let mut seed = [0u8; 8];
+    seed.copy_from_slice(&hash[..8]);
+    (u64::from_le_bytes(seed) % entrants as u64) as usize
+}
+
+#[derive(Accounts)]
+pub struct InitializeLotteryVulnerable<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Lottery::SPACE,
+        seeds = [LOTTERY_NAMESPACE.as_bytes(), authority.key().as_ref()],
+        bump,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnterVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [LOTTERY_NAMESPACE.as_bytes(), lottery.authority.as_ref()],
+        bump = lottery.bump
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub entrant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DrawWinnerVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [LOTTERY_NAMESPACE.as_bytes(), lottery.authority.as_ref()],
+        bump = lottery.bump
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    // VULNERABILITY: Anyone may draw, in whichever slot suits them
+    pub caller: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Lottery {
+    pub authority: Pubkey,
+    #[max_len(MAX_ENTRANTS)]
+    pub entrants: Vec<Pubkey>,
+    pub winner: Option<Pubkey>,
+    pub bump: u8,
+}
+
+impl Lottery {
+    /// Bytes to allocate: discriminator + fields, at MAX_ENTRANTS entrants
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Lottery already has a winner")]
+    AlreadyDrawn,
+
+    #[msg("Lottery is full")]
+    LotteryFull,
+
+    #[msg("Lottery has no entrants")]
+    NoEntrants,
+}
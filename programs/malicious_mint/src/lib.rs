@@ -0,0 +1,207 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+// ============================================================================
+// TEST FIXTURE: Malicious Token Program
+// ============================================================================
+//
+// WHAT IT IS:
+// A token-like program for tests that need a misbehaving "token program".
+// It accepts SPL Token style Transfer instructions, but can be told to:
+// - Skim a fee so the destination receives less than `amount`
+// - Credit a fixed number of units less than it debits
+// - Call back into the caller (reentrancy) after moving balances
+//
+// Token accounts use the SPL Token layout (165 bytes, `amount` at offset 64),
+// so code that reads balances from raw account data sees plausible values.
+//
+// INSTRUCTION DATA ENCODING:
+//   byte  0       : 3 (Transfer, same tag as SPL Token)
+//   bytes 1..9    : amount (u64, little endian)
+//   byte  9       : behavior (optional, defaults to 0)
+//                     0 = Honest       - credit exactly what is debited
+//                     1 = SkimFee      - withhold `param` basis points of amount
+//                     2 = ShortCredit  - withhold `param` units of amount
+//                     3 = Reenter      - transfer honestly, then call back
+//   bytes 10..18  : param (u64, little endian, ignored by Honest/Reenter)
+//   bytes 18..    : Reenter only - instruction data for the callback
+//
+// ACCOUNTS:
+//   0. [writable] source token account
+//   1. [writable] destination token account
+//   2. []         authority (NOT checked - this program is malicious)
+//   3. []         Reenter only - program to call back into
+//   4..           Reenter only - accounts forwarded to the callback
+//
+// NEVER deploy this anywhere but a local test validator.
+// ============================================================================
+
+solana_program::declare_id!("MaLiciousMint111111111111111111111111111111");
+
+pub const TRANSFER_TAG: u8 = 3;
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+const AMOUNT_OFFSET: usize = 64;
+
+/// How the fixture misbehaves on transfer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Behavior {
+    Honest,
+    SkimFee { bps: u64 },
+    ShortCredit { withheld: u64 },
+    Reenter { data: Vec<u8> },
+}
+
+impl Behavior {
+    fn tag(&self) -> u8 {
+        match self {
+            Behavior::Honest => 0,
+            Behavior::SkimFee { .. } => 1,
+            Behavior::ShortCredit { .. } => 2,
+            Behavior::Reenter { .. } => 3,
+        }
+    }
+
+    fn param(&self) -> u64 {
+        match self {
+            Behavior::SkimFee { bps } => *bps,
+            Behavior::ShortCredit { withheld } => *withheld,
+            Behavior::Honest | Behavior::Reenter { .. } => 0,
+        }
+    }
+}
+
+/// Builds a Transfer instruction with the given behavior.
+/// For `Behavior::Reenter`, `callback` is the program called back into
+/// and `callback_accounts` are forwarded to it.
+pub fn transfer(
+    source: Pubkey,
+    destination: Pubkey,
+    authority: Pubkey,
+    amount: u64,
+    behavior: Behavior,
+    callback: Option<(Pubkey, Vec<AccountMeta>)>,
+) -> Instruction {
+    let mut data = vec![TRANSFER_TAG];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(behavior.tag());
+    data.extend_from_slice(&behavior.param().to_le_bytes());
+    if let Behavior::Reenter { data: callback_data } = &behavior {
+        data.extend_from_slice(callback_data);
+    }
+
+    let mut accounts = vec![
+        AccountMeta::new(source, false),
+        AccountMeta::new(destination, false),
+        AccountMeta::new_readonly(authority, false),
+    ];
+    if let Some((program, forwarded)) = callback {
+        accounts.push(AccountMeta::new_readonly(program, false));
+        accounts.extend(forwarded);
+    }
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data,
+    }
+}
+
+/// Raw data for a token account holding `amount`
+pub fn token_account_data(amount: u64) -> Vec<u8> {
+    let mut data = vec![0u8; TOKEN_ACCOUNT_LEN];
+    data[AMOUNT_OFFSET..AMOUNT_OFFSET + 8].copy_from_slice(&amount.to_le_bytes());
+    data
+}
+
+/// Reads the `amount` field of a token account
+pub fn read_amount(data: &[u8]) -> Result<u64, ProgramError> {
+    let bytes = data
+        .get(AMOUNT_OFFSET..AMOUNT_OFFSET + 8)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn write_amount(account: &AccountInfo, amount: u64) -> ProgramResult {
+    let mut data = account.try_borrow_mut_data()?;
+    data.get_mut(AMOUNT_OFFSET..AMOUNT_OFFSET + 8)
+        .ok_or(ProgramError::InvalidAccountData)?
+        .copy_from_slice(&amount.to_le_bytes());
+    Ok(())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    data.get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .unwrap_or(0)
+}
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if data.first() != Some(&TRANSFER_TAG) || data.len() < 9 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount = read_u64(data, 1);
+    let behavior = data.get(9).copied().unwrap_or(0);
+    let param = read_u64(data, 10);
+
+    let iter = &mut accounts.iter();
+    let source = next_account_info(iter)?;
+    let destination = next_account_info(iter)?;
+    let _authority = next_account_info(iter)?;
+
+    let credited = match behavior {
+        0 | 3 => amount,
+        1 => amount.saturating_sub(amount.saturating_mul(param) / 10_000),
+        2 => amount.saturating_sub(param),
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    let source_amount = read_amount(&source.try_borrow_data()?)?;
+    let destination_amount = read_amount(&destination.try_borrow_data()?)?;
+    write_amount(
+        source,
+        source_amount
+            .checked_sub(amount)
+            .ok_or(ProgramError::InsufficientFunds)?,
+    )?;
+    write_amount(
+        destination,
+        destination_amount
+            .checked_add(credited)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+    )?;
+
+    if behavior == 3 {
+        let callback = next_account_info(iter)?;
+        let forwarded: Vec<AccountInfo> = iter.cloned().collect();
+        let instruction = Instruction {
+            program_id: *callback.key,
+            accounts: forwarded
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: *account.key,
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data: data.get(18..).unwrap_or_default().to_vec(),
+        };
+        let mut infos = forwarded;
+        infos.push(callback.clone());
+        invoke(&instruction, &infos)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,5 @@
+pub mod vulnerable;
+pub mod secure;
+
+pub use vulnerable::*;
+pub use secure::*;
@@ -0,0 +1,193 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount};
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Only Honour Receipts the Vault Issued
+// ============================================================================
+//
+// WHAT'S FIXED:
+// `mint` is a typed `Account<'info, Mint>` - owned by the Token program and
+// a real mint - and must name the vault PDA as its mint authority:
+//
+//     mint.mint_authority == COption::Some(vault)
+//
+// Only the vault can sign for the PDA, and it only mints against a deposit,
+// so every token of an accepted mint is backed. An attacker's own mint has
+// the attacker as authority and is rejected before anything is burned or
+// paid. The receipt token account must also hold that mint and belong to
+// the signer.
+//
+// BEST PRACTICES:
+// 1. Take mints as `Account<'info, Mint>`, never `AccountInfo`
+// 2. Check `mint_authority` (and `freeze_authority`) when a mint's supply
+//    is what gives its tokens value
+// 3. Tie token accounts to the checked mint with `token::mint`
+// 4. Or pin the one mint's address in program state
+//
+// ============================================================================
+
+declare_id!("MintAuthority111111111111111111111111111111");
+
+/// PDA namespace of the singleton Vault: `[VAULT_NAMESPACE, program id]`
+pub const VAULT_NAMESPACE: &str = "mint_authority/vault";
+
+#[program]
+pub mod mint_authority_secure {
+    use super::*;
+
+    /// Create the `[VAULT_NAMESPACE, program id]` PDA, which holds deposits and issues receipts
+    pub fn initialize_vault_safe(ctx: Context<InitializeVaultSafe>) -> Result<()> {
+        ctx.accounts.vault.bump = ctx.bumps.vault;
+        Ok(())
+    }
+
+    /// SECURE: Deposit `amount` lamports for `amount` receipt tokens
+    pub fn deposit_safe(ctx: Context<DepositSafe>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[VAULT_NAMESPACE.as_bytes(), ID.as_ref(), &[ctx.accounts.vault.bump]]];
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.receipt_token.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        msg!("Deposited {} lamports", amount);
+        Ok(())
+    }
+
+    /// SECURE: Burn `amount` receipts and pay out as many lamports
+    pub fn redeem_safe(ctx: Context<RedeemSafe>, amount: u64) -> Result<()> {
+        // SECURE: `mint` is the vault's own receipt mint, checked by the
+        // account constraints before this runs
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.receipt_token.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let vault = ctx.accounts.vault.to_account_info();
+        let owner = ctx.accounts.owner.to_account_info();
+        let vault_lamports = vault
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(CustomError::InsufficientFunds)?;
+        let owner_lamports = owner
+            .lamports()
+            .checked_add(amount)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+        **vault.try_borrow_mut_lamports()? = vault_lamports;
+        **owner.try_borrow_mut_lamports()? = owner_lamports;
+
+        msg!("Redeemed {} lamports", amount);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeVaultSafe<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Vault::SPACE,
+        seeds = [VAULT_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSafe<'info> {
+    #[account(mut, seeds = [VAULT_NAMESPACE.as_bytes(), ID.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// CONSTRAINT: A mint only the vault can issue
+    #[account(
+        mut,
+        constraint = mint.mint_authority == COption::Some(vault.key())
+            @ CustomError::InvalidMintAuthority,
+    )]
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut, token::mint = mint)]
+    pub receipt_token: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemSafe<'info> {
+    #[account(mut, seeds = [VAULT_NAMESPACE.as_bytes(), ID.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    // SECURE: Receipts are only worth lamports if the vault issued them
+    #[account(
+        mut,
+        constraint = mint.mint_authority == COption::Some(vault.key())
+            @ CustomError::InvalidMintAuthority,
+    )]
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub receipt_token: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub bump: u8,
+}
+
+impl Vault {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Mint authority is not the vault")]
+    InvalidMintAuthority,
+
+    #[msg("Vault cannot cover the redemption")]
+    InsufficientFunds,
+}
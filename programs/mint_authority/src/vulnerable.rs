@@ -0,0 +1,186 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Burn, MintTo, Token};
+
+// ============================================================================
+// VULNERABILITY: Unchecked Mint Authority
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// The vault issues receipt tokens 1:1 for the lamports deposited into it,
+// and pays lamports back when receipts are burned. Receipts are meant to
+// come from one mint, whose mint authority is the vault PDA - so only the
+// vault, and only against a deposit, can issue them.
+//
+// Both instructions take `mint` as a bare `AccountInfo` and never look at
+// it. `redeem_vulnerable` burns whatever the caller passes and pays out:
+//
+//     1. Attacker creates their own mint, with themselves as authority
+//     2. Mints themselves as many "receipts" as the vault holds
+//     3. Redeems them - the burn succeeds, and the vault pays for tokens
+//        it never issued
+//
+// WHY IT'S UNSAFE:
+// - A token is only worth what its mint authority can't do; any account
+//   can be initialized as a mint, with any authority
+// - `AccountInfo` skips even the check that it is a mint at all
+// - The vault's lamports back one mint's supply, not every mint's
+//
+// SEVERITY: CRITICAL
+// ============================================================================
+
+declare_id!("MintAuthority111111111111111111111111111111");
+
+/// PDA namespace of the singleton Vault: `[VAULT_NAMESPACE, program id]`
+pub const VAULT_NAMESPACE: &str = "mint_authority/vault";
+
+#[program]
+pub mod mint_authority {
+    use super::*;
+
+    /// Create the `[VAULT_NAMESPACE, program id]` PDA, which holds deposits and issues receipts
+    pub fn initialize_vault_vulnerable(ctx: Context<InitializeVaultVulnerable>) -> Result<()> {
+        ctx.accounts.vault.bump = ctx.bumps.vault;
+        Ok(())
+    }
+
+    /// Deposit `amount` lamports for `amount` receipt tokens
+    pub fn deposit_vulnerable(ctx: Context<DepositVulnerable>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[VAULT_NAMESPACE.as_bytes(), ID.as_ref(), &[ctx.accounts.vault.bump]]];
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.receipt_token.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        msg!("Deposited {} lamports", amount);
+        Ok(())
+    }
+
+    /// VULNERABLE: Burn `amount` receipts and pay out as many lamports
+    pub fn redeem_vulnerable(ctx: Context<RedeemVulnerable>, amount: u64) -> Result<()> {
+        // VULNERABILITY: Burns from any mint - including one the caller
+        // can mint as much of as they like
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.receipt_token.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let vault = ctx.accounts.vault.to_account_info();
+        let owner = ctx.accounts.owner.to_account_info();
+        let vault_lamports = vault
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(CustomError::InsufficientFunds)?;
+        let owner_lamports = owner
+            .lamports()
+            .checked_add(amount)
+            .ok_or(CustomError::ArithmeticOverflow)?;
+        **vault.try_borrow_mut_lamports()? = vault_lamports;
+        **owner.try_borrow_mut_lamports()? = owner_lamports;
+
+        msg!("Redeemed {} lamports", amount);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeVaultVulnerable<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Vault::SPACE,
+        seeds = [VAULT_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositVulnerable<'info> {
+    #[account(mut, seeds = [VAULT_NAMESPACE.as_bytes(), ID.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    // VULNERABILITY: Any account at all
+    #[account(mut)]
+    pub mint: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub receipt_token: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemVulnerable<'info> {
+    #[account(mut, seeds = [VAULT_NAMESPACE.as_bytes(), ID.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    // VULNERABILITY: Any account at all - the vault never checks that it
+    // issued these receipts
+    #[account(mut)]
+    pub mint: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub receipt_token: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub bump: u8,
+}
+
+impl Vault {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+
+    #[msg("Vault cannot cover the redemption")]
+    InsufficientFunds,
+}
@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Transfer, TokenAccount, Mint, Token};
+use anchor_spl::token::{TokenAccount, Mint, Token};
+use anchor_spl::token_interface::{self, GetAccountDataSize, InitializeAccount3, TokenInterface};
+use security_utils::TokenOps;
 
 // ============================================================================
 // FIX: Proper Account Validation
@@ -14,9 +16,10 @@ use anchor_spl::token::{self, Transfer, TokenAccount, Mint, Token};
 //
 // BEST PRACTICES:
 // 1. Always specify account constraints in #[account(...)] macros
-// 2. Use Anchor's SPL token helpers for token operations
+// 2. Use validated token helpers (security_utils::TokenOps) for token operations
 // 3. Validate that mints match before transfers
 // 4. Verify signer status for sensitive operations
+// 5. Ask the token program for an account's size instead of hardcoding it
 //
 // ============================================================================
 
@@ -37,19 +40,134 @@ pub mod missing_account_validation_secure {
         // 2. authority has signer status
         // 3. All accounts are properly initialized
 
-        token::transfer(
+        // SECURE: Fail with our own error rather than the token program's
+        require!(
+            ctx.accounts.token_from.amount >= amount,
+            CustomError::InsufficientBalance
+        );
+
+        // TokenOps re-checks the token program before invoking it
+        ctx.accounts.token_program.transfer(
+            ctx.accounts.token_from.to_account_info(),
+            ctx.accounts.token_to.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
+            amount,
+            &[],
+        )?;
+
+        msg!("Successfully transferred {} tokens", amount);
+        Ok(())
+    }
+
+    /// SECURE: Transfers tokens, stating the decimals the caller expects
+    ///
+    /// `transfer_checked` makes the token program verify `decimals` against
+    /// the mint. A client that has the wrong mint in mind - say a 9 decimal
+    /// token it takes for a 6 decimal one - fails instead of moving 1000x
+    /// the value it meant to.
+    pub fn transfer_tokens_checked_safe(
+        ctx: Context<TransferSafe>,
+        amount: u64,
+        decimals: u8,
+    ) -> Result<()> {
+        ctx.accounts.token_program.transfer_checked(
+            ctx.accounts.token_from.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.token_to.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
+            amount,
+            decimals,
+            &[],
+        )?;
+
+        msg!("Successfully transferred {} tokens", amount);
+        Ok(())
+    }
+
+    /// SECURE: Initializes a client-allocated token account after checking
+    /// its size against what the token program says this mint requires
+    pub fn init_token_account_safe(
+        ctx: Context<InitTokenAccountSafe>,
+    ) -> Result<()> {
+        // SECURE: Token-2022 mints with extensions need larger accounts
+        // (e.g. a transfer-fee mint adds a TransferFeeAmount extension), so
+        // the only reliable source for the size is the token program itself.
+        // anchor_spl also checks the return data came from that program.
+        let expected_size = token_interface::get_account_data_size(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.token_from.to_account_info(),
-                    to: ctx.accounts.token_to.to_account_info(),
-                    authority: ctx.accounts.authority.to_account_info(),
+                GetAccountDataSize {
+                    mint: ctx.accounts.mint.to_account_info(),
                 },
             ),
-            amount,
+            &[],
         )?;
 
-        msg!("Successfully transferred {} tokens", amount);
+        // SECURE: Reject under-allocation (init would fail or the account
+        // couldn't hold its extensions) and over-allocation (wasted rent)
+        require_eq!(
+            ctx.accounts.token_account.data_len() as u64,
+            expected_size,
+            CustomError::AccountSizeMismatch
+        );
+
+        token_interface::initialize_account3(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            InitializeAccount3 {
+                account: ctx.accounts.token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ))?;
+
+        msg!("Initialized token account of {} bytes", expected_size);
+        Ok(())
+    }
+
+    /// SECURE: Pays every recipient or nobody
+    ///
+    /// `remaining_accounts[i]` is the token account for `recipients[i]`.
+    /// Every recipient is validated before the first transfer, so a bad
+    /// entry anywhere in the batch rejects the whole batch.
+    pub fn batch_transfer_safe<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchTransferSafe<'info>>,
+        recipients: Vec<(Pubkey, u64)>,
+    ) -> Result<()> {
+        let recipient_accounts = ctx.remaining_accounts;
+
+        // SECURE: One token account per recipient, no more and no fewer
+        require_eq!(
+            recipient_accounts.len(),
+            recipients.len(),
+            CustomError::PartialFillDetected
+        );
+
+        // PHASE 1: Validate every recipient before moving any tokens
+        for ((owner, _), info) in recipients.iter().zip(recipient_accounts) {
+            // SECURE: Must be a real token account (owned by the token program)...
+            let token_account = Account::<TokenAccount>::try_from(info)
+                .map_err(|_| CustomError::PartialFillDetected)?;
+
+            // SECURE: ...for this mint, belonging to the listed recipient
+            require!(
+                token_account.mint == ctx.accounts.mint.key() && token_account.owner == *owner,
+                CustomError::PartialFillDetected
+            );
+        }
+
+        // PHASE 2: Transfer. Any CPI failure aborts the whole transaction,
+        // so there is no way to end up with only some recipients paid.
+        for ((_, amount), info) in recipients.iter().zip(recipient_accounts) {
+            ctx.accounts.token_program.transfer(
+                ctx.accounts.token_from.to_account_info(),
+                info.clone(),
+                ctx.accounts.authority.to_account_info(),
+                *amount,
+                &[],
+            )?;
+        }
+
+        msg!("Transferred to all {} recipients", recipients.len());
         Ok(())
     }
 }
@@ -64,7 +182,7 @@ pub struct TransferSafe<'info> {
     /// CONSTRAINT: Must be mutable (we're updating balance)
     #[account(
         mut,
-        associated_token_account::mint = mint,
+        token::mint = mint,
     )]
     pub token_from: Account<'info, TokenAccount>,
 
@@ -73,7 +191,7 @@ pub struct TransferSafe<'info> {
     /// CONSTRAINT: Must be mutable
     #[account(
         mut,
-        associated_token_account::mint = mint,
+        token::mint = mint,
     )]
     pub token_to: Account<'info, TokenAccount>,
 
@@ -85,3 +203,50 @@ pub struct TransferSafe<'info> {
     /// The token program (standard Solana token program)
     pub token_program: Program<'info, Token>,
 }
+
+#[derive(Accounts)]
+pub struct BatchTransferSafe<'info> {
+    /// The mint every recipient account must belong to
+    pub mint: Account<'info, Mint>,
+
+    /// CONSTRAINT: Must belong to the same mint
+    #[account(
+        mut,
+        token::mint = mint,
+    )]
+    pub token_from: Account<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    // remaining_accounts: one recipient token account per entry in `recipients`
+}
+
+#[derive(Accounts)]
+pub struct InitTokenAccountSafe<'info> {
+    /// Allocated by the client and assigned to the token program;
+    /// the token program rejects it if it is already initialized
+    #[account(mut)]
+    pub token_account: AccountInfo<'info>,
+
+    /// CONSTRAINT: Must be a mint owned by SPL Token or Token-2022
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// Will own the new token account
+    pub owner: AccountInfo<'info>,
+
+    /// CONSTRAINT: Must be SPL Token or Token-2022
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Token account size does not match what the token program requires")]
+    AccountSizeMismatch,
+
+    #[msg("A recipient failed validation; the batch would only partially fill")]
+    PartialFillDetected,
+
+    #[msg("Source token account balance is below the transfer amount")]
+    InsufficientBalance,
+}
@@ -1,4 +1,7 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token::{self, spl_token, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{self, InitializeAccount3, TokenInterface};
 
 // ============================================================================
 // VULNERABILITY: Missing Account Validation
@@ -33,25 +36,137 @@ pub mod missing_account_validation {
         // VULNERABILITY: We accept any token account without checking:
         // 1. That token_from belongs to the correct mint
         // 2. That token_to belongs to the correct mint
-        // 3. That token_from has enough balance (though we check later)
+        // 3. That token_from has enough balance
         // 4. Account ownership or initialization status
+        // 5. That the authority signed
 
         let from_account = &ctx.accounts.token_from;
         let to_account = &ctx.accounts.token_to;
+        let authority = &ctx.accounts.authority;
 
-        // Dangerously assume these are valid token accounts and transfer
-        // In reality, we should use anchor_spl token_transfer helper
-        // or manually validate account structure
-        
         msg!("Transferring {} tokens", amount);
-        
-        // This would fail at runtime but demonstrates the principle:
-        // We're not validating the account structure at all
-        
+
+        // VULNERABILITY: Dangerously assume these are valid token accounts
+        // and hand them straight to the token program. Whatever it happens
+        // to check (matching mints, the owner's signature) is the only
+        // validation they get - and it knows nothing about which mint or
+        // which accounts this program meant to move.
+        let ix = spl_token::instruction::transfer(
+            &spl_token::ID,
+            from_account.key,
+            to_account.key,
+            authority.key,
+            &[],
+            amount,
+        )?;
+        invoke(
+            &ix,
+            &[
+                from_account.clone(),
+                to_account.clone(),
+                authority.clone(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// VULNERABLE: Initializes a client-allocated token account, checking
+    /// its size against a hardcoded constant
+    pub fn init_token_account_unsafe(
+        ctx: Context<InitTokenAccountUnsafe>,
+    ) -> Result<()> {
+        // VULNERABILITY: 165 is only the size of a classic SPL Token account.
+        // Token-2022 mints with extensions need more, and ">=" also lets
+        // clients over-allocate. Too-small accounts slip through this check
+        // and fail later inside the token program; too-large ones just
+        // waste rent.
+        require!(
+            ctx.accounts.token_account.data_len() >= TOKEN_ACCOUNT_LEN,
+            CustomError::AccountTooSmall
+        );
+
+        token_interface::initialize_account3(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            InitializeAccount3 {
+                account: ctx.accounts.token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ))?;
+
+        msg!("Initialized token account");
+        Ok(())
+    }
+
+    /// VULNERABLE: Pays recipients one by one, skipping any that look wrong
+    pub fn batch_transfer_unsafe<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchTransferUnsafe<'info>>,
+        recipients: Vec<(Pubkey, u64)>,
+    ) -> Result<()> {
+        for ((_, amount), info) in recipients.iter().zip(ctx.remaining_accounts) {
+            // VULNERABILITY: A bad recipient is skipped instead of failing
+            // the batch. Everyone before and after it still gets paid, so
+            // the batch "succeeds" with only part of the payout done and
+            // no record of which entries were dropped.
+            if TokenAccount::try_deserialize(&mut &info.data.borrow()[..]).is_err() {
+                msg!("Skipping invalid recipient {}", info.key());
+                continue;
+            }
+
+            // VULNERABILITY: The mint and owner were never compared against
+            // the batch, so tokens can go to someone other than the listed
+            // recipient
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.token_from.to_account_info(),
+                        to: info.clone(),
+                        authority: ctx.accounts.authority.to_account_info(),
+                    },
+                ),
+                *amount,
+            )?;
+        }
+
+        msg!("Batch transfer done");
+        Ok(())
+    }
+
+    /// VULNERABLE: Checks a transfer of `amount` before it is made - except
+    /// for the mints
+    ///
+    /// Each check that is here fails with its own CustomError; the one
+    /// that isn't lets accounts on different mints through.
+    pub fn validate_unsafe(ctx: Context<ValidateUnsafe>, amount: u64) -> Result<()> {
+        let token_from = read_token_account(&ctx.accounts.token_from)?;
+        read_token_account(&ctx.accounts.token_to)?;
+
+        require_keys_eq!(
+            token_from.owner,
+            ctx.accounts.authority.key(),
+            CustomError::NotTokenOwner
+        );
+        require!(token_from.amount >= amount, CustomError::InsufficientBalance);
+
+        // VULNERABILITY: Never compares token_from.mint with token_to.mint,
+        // or either with the mint this transfer is meant to move
+        msg!("Validated transfer of {} tokens", amount);
         Ok(())
     }
 }
 
+// VULNERABILITY: Hardcoded account size
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+
+/// `info`'s data as a token account, or NotATokenAccount
+fn read_token_account(info: &AccountInfo) -> Result<TokenAccount> {
+    TokenAccount::try_deserialize(&mut &info.data.borrow()[..])
+        .map_err(|_| CustomError::NotATokenAccount.into())
+}
+
 #[derive(Accounts)]
 pub struct TransferUnsafe<'info> {
     /// VULNERABILITY: No validation that this is from the correct mint
@@ -64,4 +179,54 @@ pub struct TransferUnsafe<'info> {
     
     /// The authority - but we don't verify they signed!
     pub authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BatchTransferUnsafe<'info> {
+    #[account(mut)]
+    pub token_from: AccountInfo<'info>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ValidateUnsafe<'info> {
+    /// VULNERABILITY: Read as a token account, but its mint is never checked
+    pub token_from: AccountInfo<'info>,
+
+    /// VULNERABILITY: Read as a token account, but its mint is never checked
+    pub token_to: AccountInfo<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitTokenAccountUnsafe<'info> {
+    #[account(mut)]
+    pub token_account: AccountInfo<'info>,
+
+    pub mint: AccountInfo<'info>,
+
+    pub owner: AccountInfo<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Token account is too small")]
+    AccountTooSmall,
+
+    #[msg("Account is not an initialized token account")]
+    NotATokenAccount,
+
+    #[msg("Source token account does not belong to the authority")]
+    NotTokenOwner,
+
+    #[msg("Source token account balance is below the transfer amount")]
+    InsufficientBalance,
 }
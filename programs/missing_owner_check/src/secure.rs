@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Owner-Checked Config
+// ============================================================================
+//
+// WHAT'S FIXED:
+// The config is taken as `Account<'info, Config>`. Before the handler runs,
+// Anchor checks that the account is owned by this program (failing with
+// AccountOwnedByWrongProgram) and only then reads its data. A look-alike
+// account owned by the System Program - or any other program - never gets
+// as far as its fields.
+//
+// The field checks move into `has_one` constraints, which now compare
+// against data only this program could have written.
+//
+// BEST PRACTICES:
+// 1. Use `Account<'info, T>` for program state: owner and discriminator
+//    are both checked
+// 2. If you must take an AccountInfo, check `*account.owner == crate::ID`
+//    before reading a single byte
+// 3. Pin singletons like a config to their address too (`seeds` or
+//    `address`), so only the one real config is accepted
+//
+// ============================================================================
+
+declare_id!("ForgedConfig1111111111111111111111111111111");
+
+#[program]
+pub mod missing_owner_check_secure {
+    use super::*;
+
+    /// SECURE: Pay out of the treasury to the configured admin
+    pub fn withdraw_treasury_secure(
+        ctx: Context<WithdrawTreasurySecure>,
+        amount: u64,
+    ) -> Result<()> {
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let admin = ctx.accounts.admin.to_account_info();
+        **treasury.try_borrow_mut_lamports()? = treasury
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(CustomError::InsufficientFunds)?;
+        **admin.try_borrow_mut_lamports()? = admin
+            .lamports()
+            .checked_add(amount)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+
+        msg!("Paid {} lamports from the treasury to {}", amount, admin.key());
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasurySecure<'info> {
+    /// SECURE: Must be owned by this program; fields then trusted
+    #[account(
+        has_one = admin @ SecurityError::Unauthorized,
+        has_one = treasury,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[account]
+pub struct Config {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+}
+
+#[account]
+pub struct Treasury {}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Treasury has insufficient funds")]
+    InsufficientFunds,
+}
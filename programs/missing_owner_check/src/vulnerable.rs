@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+
+// ============================================================================
+// VULNERABILITY: Missing Owner Check
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// This program reads its Config from a raw AccountInfo. It checks the
+// discriminator and every field it cares about - but never who owns the
+// account.
+//
+// Only the owning program can write an account's data, so the owner is the
+// one thing that vouches for it. Without that check, the bytes are whatever
+// the caller put there. Anyone can create an account under the System
+// Program (or a program of their own) holding:
+//
+//     [Config discriminator] [admin = attacker] [treasury = real treasury]
+//
+// and this program will treat it as its own Config.
+//
+// WHY IT'S UNSAFE:
+// - The discriminator is public: the first 8 bytes of sha256("account:Config")
+// - Field checks compare forged data against itself
+// - The attacker becomes "admin" and drains the treasury
+//
+// SEVERITY: CRITICAL
+// ============================================================================
+
+declare_id!("ForgedConfig1111111111111111111111111111111");
+
+#[program]
+pub mod missing_owner_check {
+    use super::*;
+
+    /// VULNERABLE: Pay out of the treasury to the configured admin
+    pub fn withdraw_treasury_vulnerable(
+        ctx: Context<WithdrawTreasuryVulnerable>,
+        amount: u64,
+    ) -> Result<()> {
+        // VULNERABILITY: try_deserialize checks the discriminator, not the
+        // owner - a look-alike account from any program passes
+        let config = Config::try_deserialize(&mut &ctx.accounts.config.try_borrow_data()?[..])?;
+        require_keys_eq!(config.admin, ctx.accounts.admin.key(), CustomError::Unauthorized);
+        require_keys_eq!(
+            config.treasury,
+            ctx.accounts.treasury.key(),
+            CustomError::TreasuryMismatch
+        );
+
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let admin = ctx.accounts.admin.to_account_info();
+        **treasury.try_borrow_mut_lamports()? = treasury
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(CustomError::InsufficientFunds)?;
+        **admin.try_borrow_mut_lamports()? = admin
+            .lamports()
+            .checked_add(amount)
+            .ok_or(CustomError::ArithmeticOverflow)?;
+
+        msg!("Paid {} lamports from the treasury to {}", amount, config.admin);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasuryVulnerable<'info> {
+    /// VULNERABILITY: Owner never checked
+    pub config: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[account]
+pub struct Config {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+}
+
+#[account]
+pub struct Treasury {}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Signer is not the config admin")]
+    Unauthorized,
+
+    #[msg("Treasury does not match the config")]
+    TreasuryMismatch,
+
+    #[msg("Treasury has insufficient funds")]
+    InsufficientFunds,
+
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+}
@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use security_utils::safe_math::{safe_add, safe_mul_div};
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Multiply First, in u128
+// ============================================================================
+//
+// WHAT'S FIXED:
+// The share is computed in the order that keeps the precision:
+//
+//     safe_mul_div(amount, epoch_rewards, total_staked)
+//
+// which multiplies in u128 and divides once, at the end. The only rounding
+// is that final division, so a staker is short by less than one reward unit,
+// never by a multiple of their stake. The u128 product can't overflow, so
+// multiplying first costs nothing in range.
+//
+// BEST PRACTICES:
+// 1. Multiply before dividing: `a * c / b`, never `(a / b) * c`
+// 2. Take the product in u128 so the reordering doesn't overflow
+// 3. Round once, at the end, and in the protocol's favour
+// 4. Compare against the exact rational result in tests
+//
+// ============================================================================
+
+declare_id!("Precision1111111111111111111111111111111111");
+
+#[program]
+pub mod precision_ordering_secure {
+    use super::*;
+
+    /// SECURE: Credit this epoch's reward to the caller's stake
+    pub fn claim_rewards_safe(ctx: Context<ClaimRewardsSafe>) -> Result<()> {
+        let stake = &mut ctx.accounts.stake;
+        require!(!stake.claimed, CustomError::AlreadyClaimed);
+
+        let reward = pending_reward(&ctx.accounts.pool, stake.amount)?;
+        stake.rewards = safe_add(stake.rewards, reward)?;
+        stake.claimed = true;
+
+        msg!("Credited {} rewards", reward);
+        Ok(())
+    }
+}
+
+/// Reward owed this epoch on `amount` staked in `pool`, rounded down
+///
+/// Clients can call this to show the reward a claim will credit.
+pub fn pending_reward(pool: &RewardPool, amount: u64) -> Result<u64> {
+    // SECURE: One u128 multiplication, then one division
+    safe_mul_div(amount, pool.epoch_rewards, pool.total_staked)
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewardsSafe<'info> {
+    pub pool: Account<'info, RewardPool>,
+
+    #[account(
+        mut,
+        has_one = pool @ CustomError::PoolMismatch,
+        has_one = owner @ SecurityError::Unauthorized,
+    )]
+    pub stake: Account<'info, StakeAccount>,
+
+    pub owner: Signer<'info>,
+}
+
+#[account]
+pub struct RewardPool {
+    pub total_staked: u64,
+    pub epoch_rewards: u64, // Shared pro rata among all stakes
+}
+
+#[account]
+pub struct StakeAccount {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub rewards: u64, // Credited, not yet withdrawn
+    pub claimed: bool,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Stake belongs to a different pool")]
+    PoolMismatch,
+
+    #[msg("This epoch's reward was already claimed")]
+    AlreadyClaimed,
+}
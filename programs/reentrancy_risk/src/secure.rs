@@ -18,77 +18,146 @@ use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 // 3. Use reentrancy guards (locked flags) if necessary
 // 4. Mark state as "in-progress" before CPI
 // 5. Understand Solana's call stack prevents self-reentrancy
+// 6. Make per-user records PDAs bound to their owner and pool
 //
 // ============================================================================
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use security_utils::{pda, validate, SecurityError};
 
 declare_id!("55555555555555555555555555555555");
 
+/// Slots after which a lock that was never released counts as stuck
+pub const LOCK_TIMEOUT_SLOTS: u64 = 150;
+
+/// Delay between announcing an emergency withdrawal and executing it
+pub const EMERGENCY_TIMELOCK_SECONDS: i64 = 2 * 24 * 60 * 60;
+
+/// Fee charged on `withdraw_with_fee_safe`, in basis points of the amount
+pub const WITHDRAW_FEE_BPS: u64 = 30;
+
+/// Most mints a multi-asset pool can support
+pub const MAX_POOL_MINTS: usize = 4;
+
+/// PDA namespace of the pool vault authority: `[POOL_SIGNER_NAMESPACE, pool]`
+pub const POOL_SIGNER_NAMESPACE: &str = "reentrancy_risk/pool_signer";
+
+/// PDA namespace of a UserDeposit record: `[USER_DEPOSIT_NAMESPACE, pool, owner]`
+pub const USER_DEPOSIT_NAMESPACE: &str = "reentrancy_risk/user_deposit";
+
+/// PDA namespace of a MultiAssetDeposit record:
+/// `[MULTI_DEPOSIT_NAMESPACE, pool, owner]`
+pub const MULTI_DEPOSIT_NAMESPACE: &str = "reentrancy_risk/multi_deposit";
+
 #[program]
 pub mod reentrancy_risk_secure {
     use super::*;
 
     /// SECURE: Withdraw with Checks-Effects-Interactions pattern
+    ///
+    /// Returns a `WithdrawReceipt`, which Anchor sets as the instruction's
+    /// return data for clients and CPI callers.
     pub fn withdraw_safe(
         ctx: Context<WithdrawSafe>,
         amount: u64,
-    ) -> Result<()> {
+    ) -> Result<WithdrawReceipt> {
+        let pool_key = ctx.accounts.pool.key();
+
+        // SECURE: Only this program can sign as the pool_signer PDA
+        let signer_bump = [ctx.accounts.pool.signer_bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[POOL_SIGNER_NAMESPACE.as_bytes(), pool_key.as_ref(), &signer_bump]];
+        let mut transferer = CpiTransferer {
+            token_program: ctx.accounts.token_program.to_account_info(),
+            from: ctx.accounts.pool_token.to_account_info(),
+            to: ctx.accounts.user_token.to_account_info(),
+            authority: ctx.accounts.pool_signer.to_account_info(),
+            signer_seeds,
+        };
+
         let pool = &mut ctx.accounts.pool;
         let user = &mut ctx.accounts.user_deposit;
 
-        // SECURE: Pattern is Checks-Effects-Interactions (CORRECT!)
+        // SECURE: Pattern is Checks-Effects-Interactions (CORRECT!) - see
+        // PoolSafe::withdraw_with for the three phases
+        pool.withdraw_with(user, amount, Clock::get()?.slot, &mut transferer)?;
 
-        // PHASE 1: CHECKS - Verify preconditions
-        require!(
-            user.balance >= amount,
-            CustomError::InsufficientBalance
-        );
+        emit!(WithdrawEvent {
+            user: user.owner,
+            amount,
+            new_total: user.balance,
+        });
 
-        require!(
-            pool.total_available >= amount,
-            CustomError::InsufficientPoolFunds
-        );
+        msg!("Safely withdrew {} tokens", amount);
+        Ok(WithdrawReceipt {
+            new_balance: user.balance,
+            withdrawn: amount,
+        })
+    }
 
-        // Additional security: Check pool is not locked (reentrancy guard)
-        require!(
-            !pool.locked,
-            CustomError::PoolLocked
-        );
+    /// SECURE: Withdraw `amount` less a fee that stays in the pool
+    ///
+    /// The user's balance and the pool totals are debited by the full
+    /// `amount`; `net` is transferred out and `fee` accrues to `total_fees`.
+    /// `net` is derived as `amount - fee`, so the two always add back up to
+    /// `amount` and the vault keeps backing `total_available + total_fees`.
+    pub fn withdraw_with_fee_safe(
+        ctx: Context<WithdrawSafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let pool_key = ctx.accounts.pool.key();
+        let pool = &mut ctx.accounts.pool;
+        let user = &mut ctx.accounts.user_deposit;
 
-        // PHASE 2: EFFECTS - Update state FIRST (before external calls)
-        // Lock the pool to prevent reentrancy
-        pool.locked = true;
+        // CHECKS
+        validate!(
+            !pool.locked => CustomError::PoolLocked,
+            user.balance >= amount => CustomError::InsufficientBalance,
+            pool.total_available >= amount => CustomError::InsufficientPoolFunds,
+        )?;
 
-        user.balance = user.balance.checked_sub(amount)
-            .ok_or(CustomError::ArithmeticUnderflow)?;
+        // SECURE: Fee rounds down, and the net is what's left of the amount
+        let fee = amount
+            .checked_mul(WITHDRAW_FEE_BPS)
+            .ok_or(SecurityError::ArithmeticOverflow)?
+            / 10_000;
+        let net = amount.checked_sub(fee).ok_or(SecurityError::ArithmeticUnderflow)?;
+        let total_fees = pool
+            .total_fees
+            .checked_add(fee)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
 
-        pool.total_deposited = pool.total_deposited.checked_sub(amount)
-            .ok_or(CustomError::ArithmeticUnderflow)?;
+        // EFFECTS
+        pool.try_withdraw(user, amount)?;
+        pool.total_fees = total_fees;
 
-        pool.total_available = pool.total_available.checked_sub(amount)
-            .ok_or(CustomError::ArithmeticUnderflow)?;
+        // INTERACTIONS - Only the net leaves the vault
+        let signer_bump = [pool.signer_bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[POOL_SIGNER_NAMESPACE.as_bytes(), pool_key.as_ref(), &signer_bump]];
+        pool.with_lock(Clock::get()?.slot, |_| {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.pool_token.to_account_info(),
+                        to: ctx.accounts.user_token.to_account_info(),
+                        authority: ctx.accounts.pool_signer.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                net,
+            )
+        })?;
 
-        // PHASE 3: INTERACTIONS - External calls happen LAST
-        // By this point, the user's balance is already reduced
-        // Even if attacker re-enters, they see the updated balance
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.pool_token.to_account_info(),
-                    to: ctx.accounts.user_token.to_account_info(),
-                    authority: ctx.accounts.pool_signer.to_account_info(),
-                },
-            ),
+        emit!(WithdrawEvent {
+            user: user.owner,
             amount,
-        )?;
-
-        // Unlock the pool after successful transfer
-        pool.locked = false;
+            new_total: user.balance,
+        });
 
-        msg!("Safely withdrew {} tokens", amount);
+        msg!("Safely withdrew {} tokens ({} net, {} fee)", amount, net, fee);
         Ok(())
     }
 
@@ -99,53 +168,408 @@ pub mod reentrancy_risk_secure {
         let pool = &mut ctx.accounts.pool;
         pool.total_deposited = 0;
         pool.total_available = 0;
+        pool.total_fees = 0;
         pool.locked = false; // SECURE: Initialize reentrancy guard
+        pool.lock_acquired_slot = 0;
+        pool.admin = ctx.accounts.authority.key();
+        pool.emergency_unlock_at = 0;
+        pool.mint = ctx.accounts.mint.key(); // SECURE: Fixed for the pool's lifetime
+
+        // SECURE: Store the canonical bump of the vault authority PDA
+        let (_, signer_bump) = pda::derive(POOL_SIGNER_NAMESPACE, &pool.key(), ctx.program_id)?;
+        pool.signer_bump = signer_bump;
 
         msg!("Pool initialized with reentrancy protection");
         Ok(())
     }
 
+    /// SECURE: Open a deposit record for the signer in this pool
+    ///
+    /// This is the UserDeposit initializer: it binds `owner` to the signer
+    /// and starts the balance at 0. The record lives at the PDA
+    /// `[USER_DEPOSIT_NAMESPACE, pool, owner]`, so each owner has exactly one
+    /// per pool and it can't be swapped for another. The pool is part of the
+    /// seeds because deposits and withdrawals check the record against their
+    /// pool; a record keyed by the owner alone would be shared by every pool.
+    /// Clients find it with `UserDeposit::address`; the bump is stored on it.
+    pub fn open_deposit_safe(
+        ctx: Context<OpenDepositSafe>,
+    ) -> Result<()> {
+        let user = &mut ctx.accounts.user_deposit;
+        user.owner = ctx.accounts.owner.key();
+        user.pool = ctx.accounts.pool.key();
+        user.balance = 0;
+        user.last_deposit_id = 0;
+        user.bump = ctx.bumps.user_deposit;
+
+        msg!("Opened deposit record for {}", user.owner);
+        Ok(())
+    }
+
+    /// SECURE: Close an emptied deposit record and refund its rent
+    ///
+    /// `close = owner` moves the lamports, reassigns the account to the
+    /// System Program and truncates its data - discriminator included - so
+    /// even if someone refunds it in the same transaction it can never be
+    /// loaded as a UserDeposit again. Reopening goes through
+    /// `open_deposit_safe`, whose `init` rewrites every field and bump.
+    pub fn close_deposit_safe(
+        ctx: Context<CloseDepositSafe>,
+    ) -> Result<()> {
+        // SECURE: A record with a balance must be withdrawn first; closing
+        // it would strand the funds behind data that no longer exists
+        require!(
+            ctx.accounts.user_deposit.balance == 0,
+            CustomError::StaleAccountData
+        );
+
+        msg!("Closed deposit record for {}", ctx.accounts.owner.key());
+        Ok(())
+    }
+
     /// SECURE: Alternative - Deposit function with CEI pattern
+    ///
+    /// `deposit_id` is an optional idempotency key. Clients that retry
+    /// failed submissions should pass a per-user increasing counter; a
+    /// deposit whose id is not greater than the last one recorded is
+    /// rejected instead of being processed twice.
+    ///
+    /// Takes the same `locked` guard as `withdraw_safe` for the duration of
+    /// its transfer. Reentrancy isn't limited to the instruction being
+    /// executed: a hook that ran mid-deposit could call withdraw, which
+    /// would see the deposit's credits before its tokens had arrived.
     pub fn deposit_safe(
         ctx: Context<DepositSafe>,
         amount: u64,
+        deposit_id: Option<u64>,
     ) -> Result<()> {
         let pool = &mut ctx.accounts.pool;
         let user = &mut ctx.accounts.user_deposit;
 
         // CHECKS
-        require!(amount > 0, CustomError::InvalidAmount);
+        require!(amount > 0, SecurityError::InvalidAmount);
+        // SECURE: The reentrancy guard covers every instruction that mutates
+        // the pool, not just withdraw - re-entering through a different
+        // instruction mid-withdraw would otherwise see half-updated state
+        require!(!pool.locked, CustomError::PoolLocked);
         require!(
             ctx.accounts.user_token.amount >= amount,
             CustomError::InsufficientBalance
         );
 
+        // SECURE: Idempotency - ids only move forward, so a retried
+        // deposit (or an older one replayed) can't be credited twice
+        if let Some(id) = deposit_id {
+            require!(
+                id > user.last_deposit_id,
+                CustomError::DuplicateDepositId
+            );
+        }
+
         // EFFECTS - Update state first
-        user.balance = user.balance.checked_add(amount)
-            .ok_or(CustomError::ArithmeticOverflow)?;
+        pool.try_deposit(user, amount)?;
+        if let Some(id) = deposit_id {
+            user.last_deposit_id = id;
+        }
+
+        // INTERACTIONS - Transfer user's tokens to pool, with the pool
+        // locked until they have arrived
+        pool.with_lock(Clock::get()?.slot, |_| {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.user_token.to_account_info(),
+                        to: ctx.accounts.pool_token.to_account_info(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                amount,
+            )
+        })?;
+
+        emit!(DepositEvent {
+            user: user.owner,
+            amount,
+            new_total: user.balance,
+        });
+
+        msg!("Safely deposited {} tokens", amount);
+        Ok(())
+    }
 
-        pool.total_deposited = pool.total_deposited.checked_add(amount)
-            .ok_or(CustomError::ArithmeticOverflow)?;
+    /// SECURE: Announce an emergency withdrawal, starting its timelock
+    ///
+    /// Emergency withdrawal exists to recover funds from a bricked or
+    /// insolvent pool, and it is a centralization tradeoff: the admin can
+    /// move every token in the vault. The announcement and timelock are what
+    /// keep it honest - depositors see the request on-chain and have
+    /// `EMERGENCY_TIMELOCK_SECONDS` to withdraw before it can execute.
+    pub fn request_emergency_withdraw_safe(
+        ctx: Context<RequestEmergencyWithdrawSafe>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let unlock_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(EMERGENCY_TIMELOCK_SECONDS)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+        pool.emergency_unlock_at = unlock_at;
 
-        pool.total_available = pool.total_available.checked_add(amount)
-            .ok_or(CustomError::ArithmeticOverflow)?;
+        emit!(EmergencyWithdrawRequested {
+            admin: pool.admin,
+            unlock_at,
+        });
 
-        // INTERACTIONS - Transfer user's tokens to pool
+        msg!("Emergency withdrawal unlocks at {}", unlock_at);
+        Ok(())
+    }
+
+    /// SECURE: Drain the pool vault to the admin once the timelock has passed
+    pub fn emergency_withdraw_safe(
+        ctx: Context<EmergencyWithdrawSafe>,
+    ) -> Result<()> {
+        let pool_key = ctx.accounts.pool.key();
+        let pool = &mut ctx.accounts.pool;
+        let now = Clock::get()?.unix_timestamp;
+
+        // SECURE: Only an announced request that has waited out its timelock
+        require!(
+            pool.emergency_unlock_at != 0 && now >= pool.emergency_unlock_at,
+            CustomError::EmergencyTimelockActive
+        );
+
+        // EFFECTS: A request executes once, and the vault no longer backs
+        // any deposits. Balances stay recorded as claims on the recovered funds.
+        let amount = ctx.accounts.pool_token.amount;
+        pool.emergency_unlock_at = 0;
+        pool.total_available = 0;
+
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[POOL_SIGNER_NAMESPACE.as_bytes(), pool_key.as_ref(), &[pool.signer_bump]]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.pool_signer.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        // SECURE: The drain is visible to indexers as what it is
+        emit!(EmergencyWithdrawEvent {
+            admin: pool.admin,
+            destination: ctx.accounts.destination.key(),
+            amount,
+            timestamp: now,
+        });
+
+        msg!("Emergency withdrew {} tokens", amount);
+        Ok(())
+    }
+
+    /// SECURE: Release a lock that has been held for too long
+    ///
+    /// Within one transaction this can't happen: an error rolls back the
+    /// `locked = true` along with everything else. It matters once a lock
+    /// spans transactions (a multi-step flow that sets it in one and clears
+    /// it in another) - if the second step never comes, the pool would be
+    /// frozen for good. Only the admin may clear, and only a stale lock.
+    pub fn clear_stale_lock_safe(
+        ctx: Context<ClearStaleLockSafe>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let slot = Clock::get()?.slot;
+
+        require!(
+            pool.locked && slot.saturating_sub(pool.lock_acquired_slot) >= LOCK_TIMEOUT_SLOTS,
+            CustomError::LockNotStale
+        );
+
+        let held_since = pool.lock_acquired_slot;
+        pool.locked = false;
+        pool.lock_acquired_slot = 0;
+
+        msg!("Cleared lock held since slot {}", held_since);
+        Ok(())
+    }
+
+    /// SECURE: Initialize a pool that holds several mints
+    ///
+    /// The supported mints are fixed here. Each has its own vault, owned by
+    /// the pool's `pool_signer` PDA, and its own running total, so tokens of
+    /// one mint are never counted (or paid out) as another.
+    pub fn initialize_multi_pool_safe(
+        ctx: Context<InitializeMultiPoolSafe>,
+        mints: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(mints.len() <= MAX_POOL_MINTS, CustomError::TooManyMints);
+        for (i, mint) in mints.iter().enumerate() {
+            require!(!mints[..i].contains(mint), CustomError::DuplicateMint);
+        }
+
+        let pool = &mut ctx.accounts.pool;
+        pool.admin = ctx.accounts.authority.key();
+        pool.assets = mints
+            .into_iter()
+            .map(|mint| AssetBalance {
+                mint,
+                total_deposited: 0,
+            })
+            .collect();
+
+        let (_, signer_bump) = pda::derive(POOL_SIGNER_NAMESPACE, &pool.key(), ctx.program_id)?;
+        pool.signer_bump = signer_bump;
+
+        msg!("Multi-asset pool initialized with {} mints", pool.assets.len());
+        Ok(())
+    }
+
+    /// SECURE: Open the signer's deposit record in a multi-asset pool
+    pub fn open_multi_deposit_safe(
+        ctx: Context<OpenMultiDepositSafe>,
+    ) -> Result<()> {
+        let user = &mut ctx.accounts.user_deposit;
+        user.owner = ctx.accounts.owner.key();
+        user.pool = ctx.accounts.pool.key();
+        user.balances = [0; MAX_POOL_MINTS];
+        user.bump = ctx.bumps.user_deposit;
+
+        msg!("Opened multi-asset deposit record for {}", user.owner);
+        Ok(())
+    }
+
+    /// SECURE: Deposit one of the pool's supported mints
+    pub fn multi_deposit_safe(
+        ctx: Context<MultiAssetTransferSafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let user = &mut ctx.accounts.user_deposit;
+
+        // CHECKS
+        require!(amount > 0, SecurityError::InvalidAmount);
+        // SECURE: The mint picks the slot that gets credited; a mint the
+        // pool wasn't set up with has no slot and is rejected
+        let index = pool.asset_index(&ctx.accounts.mint.key())?;
+
+        let (balance, total_deposited) = match (
+            user.balances[index].checked_add(amount),
+            pool.assets[index].total_deposited.checked_add(amount),
+        ) {
+            (Some(balance), Some(deposited)) => (balance, deposited),
+            _ => return err!(SecurityError::ArithmeticOverflow),
+        };
+
+        // EFFECTS
+        user.balances[index] = balance;
+        pool.assets[index].total_deposited = total_deposited;
+
+        // INTERACTIONS
         token::transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
                     from: ctx.accounts.user_token.to_account_info(),
                     to: ctx.accounts.pool_token.to_account_info(),
-                    authority: ctx.accounts.user_authority.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
                 },
             ),
             amount,
         )?;
 
-        msg!("Safely deposited {} tokens", amount);
+        msg!("Safely deposited {} of mint {}", amount, ctx.accounts.mint.key());
         Ok(())
     }
+
+    /// SECURE: Withdraw one of the pool's supported mints
+    ///
+    /// Only what was deposited of that same mint can come out of its vault.
+    pub fn multi_withdraw_safe(
+        ctx: Context<MultiAssetTransferSafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let pool_key = ctx.accounts.pool.key();
+        let pool = &mut ctx.accounts.pool;
+        let user = &mut ctx.accounts.user_deposit;
+
+        // CHECKS
+        let index = pool.asset_index(&ctx.accounts.mint.key())?;
+        let balance = user.balances[index]
+            .checked_sub(amount)
+            .ok_or(CustomError::InsufficientBalance)?;
+        let total_deposited = pool.assets[index]
+            .total_deposited
+            .checked_sub(amount)
+            .ok_or(SecurityError::ArithmeticUnderflow)?;
+
+        // EFFECTS
+        user.balances[index] = balance;
+        pool.assets[index].total_deposited = total_deposited;
+
+        // INTERACTIONS
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[POOL_SIGNER_NAMESPACE.as_bytes(), pool_key.as_ref(), &[pool.signer_bump]]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token.to_account_info(),
+                    to: ctx.accounts.user_token.to_account_info(),
+                    authority: ctx.accounts.pool_signer.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        msg!("Safely withdrew {} of mint {}", amount, ctx.accounts.mint.key());
+        Ok(())
+    }
+
+    /// Emit a snapshot of the pool for off-chain monitoring
+    ///
+    /// Read-only: dashboards and alerting can call this (or simulate it)
+    /// and decode `PoolMetrics` from the logs instead of parsing raw
+    /// account data themselves.
+    pub fn emit_metrics(ctx: Context<EmitMetrics>) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+
+        emit!(PoolMetrics {
+            total_deposited: pool.total_deposited,
+            total_available: pool.total_available,
+            total_fees: pool.total_fees,
+            locked: pool.locked,
+            slot: Clock::get()?.slot,
+        });
+        Ok(())
+    }
+
+    /// Check that the vault still backs everything depositors can withdraw
+    ///
+    /// Read-only: returns a `SolvencyReport` as return data when
+    /// `pool_token.amount >= total_available`, and fails with `Insolvent`
+    /// otherwise, so monitoring can simulate it and alert on the error.
+    /// Accrued fees are held in the same vault and count as surplus.
+    pub fn check_solvency(ctx: Context<CheckSolvency>) -> Result<SolvencyReport> {
+        let vault_balance = ctx.accounts.pool_token.amount;
+        let total_available = ctx.accounts.pool.total_available;
+
+        // INVARIANT: Every withdrawable token is in the vault
+        if vault_balance < total_available {
+            msg!("Insolvent: deficit of {}", total_available - vault_balance);
+            return err!(CustomError::Insolvent);
+        }
+
+        Ok(SolvencyReport {
+            vault_balance,
+            total_available,
+            surplus: vault_balance - total_available,
+        })
+    }
 }
 
 #[derive(Accounts)]
@@ -153,16 +577,33 @@ pub struct WithdrawSafe<'info> {
     #[account(mut)]
     pub pool: Account<'info, PoolSafe>,
 
-    #[account(mut)]
+    // SECURE: The record must be this owner's PDA in this pool.
+    // has_one re-checks the stored fields in case the seeds ever change.
+    #[account(
+        mut,
+        seeds = [USER_DEPOSIT_NAMESPACE.as_bytes(), pool.key().as_ref(), owner.key().as_ref()],
+        bump = user_deposit.bump,
+        has_one = owner @ SecurityError::Unauthorized,
+        has_one = pool @ CustomError::PoolMismatch,
+    )]
     pub user_deposit: Account<'info, UserDeposit>,
 
-    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    // SECURE: Both token accounts must hold the pool's mint. Otherwise a
+    // balance credited in one mint could be paid out in another.
+    #[account(mut, constraint = pool_token.mint == pool.mint @ CustomError::MintMismatch)]
     pub pool_token: Account<'info, TokenAccount>,
 
-    #[account(mut)]
+    #[account(mut, constraint = user_token.mint == pool.mint @ CustomError::MintMismatch)]
     pub user_token: Account<'info, TokenAccount>,
 
     /// PDA that acts as authority for token account
+    /// CONSTRAINT: Must be this pool's `[POOL_SIGNER_NAMESPACE, pool]` PDA
+    #[account(
+        seeds = [POOL_SIGNER_NAMESPACE.as_bytes(), pool.key().as_ref()],
+        bump = pool.signer_bump,
+    )]
     pub pool_signer: AccountInfo<'info>,
 
     pub token_program: Program<'info, Token>,
@@ -170,9 +611,11 @@ pub struct WithdrawSafe<'info> {
 
 #[derive(Accounts)]
 pub struct InitializePoolSafe<'info> {
-    #[account(init, payer = authority, space = 8 + 8 + 8 + 1)]
+    #[account(init, payer = authority, space = PoolSafe::SPACE)]
     pub pool: Account<'info, PoolSafe>,
 
+    pub mint: Account<'info, Mint>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
 
@@ -180,36 +623,500 @@ pub struct InitializePoolSafe<'info> {
 }
 
 #[derive(Accounts)]
-pub struct DepositSafe<'info> {
-    #[account(mut)]
+pub struct OpenDepositSafe<'info> {
     pub pool: Account<'info, PoolSafe>,
 
+    #[account(
+        init,
+        payer = owner,
+        space = UserDeposit::SPACE,
+        seeds = [USER_DEPOSIT_NAMESPACE.as_bytes(), pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+
     #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDepositSafe<'info> {
+    pub pool: Account<'info, PoolSafe>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [USER_DEPOSIT_NAMESPACE.as_bytes(), pool.key().as_ref(), owner.key().as_ref()],
+        bump = user_deposit.bump,
+        has_one = owner @ SecurityError::Unauthorized,
+        has_one = pool @ CustomError::PoolMismatch,
+    )]
     pub user_deposit: Account<'info, UserDeposit>,
 
     #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSafe<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolSafe>,
+
+    // SECURE: The record must be this owner's PDA in this pool.
+    // has_one re-checks the stored fields in case the seeds ever change.
+    #[account(
+        mut,
+        seeds = [USER_DEPOSIT_NAMESPACE.as_bytes(), pool.key().as_ref(), owner.key().as_ref()],
+        bump = user_deposit.bump,
+        has_one = owner @ SecurityError::Unauthorized,
+        has_one = pool @ CustomError::PoolMismatch,
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+
+    // SECURE: Tokens must come from the depositor's own account, in the
+    // pool's mint - a deposit of any other mint would still be credited
+    #[account(
+        mut,
+        token::authority = owner,
+        constraint = user_token.mint == pool.mint @ CustomError::MintMismatch,
+    )]
     pub user_token: Account<'info, TokenAccount>,
 
+    // SECURE: Must be the vault this pool's pool_signer controls. Any other
+    // account of the pool's mint - a stranger's, or one the depositor
+    // controls and could pass back as `user_token` - would be credited as a
+    // deposit without the pool ever holding the tokens.
+    #[account(
+        mut,
+        token::authority = pool_signer,
+        constraint = pool_token.mint == pool.mint @ CustomError::MintMismatch,
+    )]
+    pub pool_token: Account<'info, TokenAccount>,
+
+    /// PDA that acts as authority for token account
+    /// CONSTRAINT: Must be this pool's `[POOL_SIGNER_NAMESPACE, pool]` PDA
+    #[account(
+        seeds = [POOL_SIGNER_NAMESPACE.as_bytes(), pool.key().as_ref()],
+        bump = pool.signer_bump,
+    )]
+    pub pool_signer: AccountInfo<'info>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RequestEmergencyWithdrawSafe<'info> {
+    #[account(mut, has_one = admin @ CustomError::NotPoolAdmin)]
+    pub pool: Account<'info, PoolSafe>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyWithdrawSafe<'info> {
+    #[account(mut, has_one = admin @ CustomError::NotPoolAdmin)]
+    pub pool: Account<'info, PoolSafe>,
+
+    pub admin: Signer<'info>,
+
+    // SECURE: Must be the vault this pool's pool_signer controls
+    #[account(mut, token::authority = pool_signer)]
+    pub pool_token: Account<'info, TokenAccount>,
+
     #[account(mut)]
+    pub destination: Account<'info, TokenAccount>,
+
+    /// PDA that acts as authority for token account
+    /// CONSTRAINT: Must be this pool's `[POOL_SIGNER_NAMESPACE, pool]` PDA
+    #[account(
+        seeds = [POOL_SIGNER_NAMESPACE.as_bytes(), pool.key().as_ref()],
+        bump = pool.signer_bump,
+    )]
+    pub pool_signer: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClearStaleLockSafe<'info> {
+    #[account(mut, has_one = admin @ CustomError::NotPoolAdmin)]
+    pub pool: Account<'info, PoolSafe>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMultiPoolSafe<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = MultiAssetPoolSafe::SPACE,
+    )]
+    pub pool: Account<'info, MultiAssetPoolSafe>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenMultiDepositSafe<'info> {
+    pub pool: Account<'info, MultiAssetPoolSafe>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = MultiAssetDeposit::SPACE,
+        seeds = [MULTI_DEPOSIT_NAMESPACE.as_bytes(), pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub user_deposit: Account<'info, MultiAssetDeposit>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for moving one mint into or out of a multi-asset pool
+#[derive(Accounts)]
+pub struct MultiAssetTransferSafe<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, MultiAssetPoolSafe>,
+
+    #[account(
+        mut,
+        seeds = [MULTI_DEPOSIT_NAMESPACE.as_bytes(), pool.key().as_ref(), owner.key().as_ref()],
+        bump = user_deposit.bump,
+        has_one = owner @ SecurityError::Unauthorized,
+        has_one = pool @ CustomError::PoolMismatch,
+    )]
+    pub user_deposit: Account<'info, MultiAssetDeposit>,
+
+    pub mint: Account<'info, Mint>,
+
+    // SECURE: Both token accounts must hold `mint`, so the slot credited or
+    // debited is the one for the tokens that actually move
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = mint, token::authority = pool_signer)]
     pub pool_token: Account<'info, TokenAccount>,
 
-    #[account(signer)]
-    pub user_authority: Signer<'info>,
+    /// PDA that acts as authority for every vault of the pool
+    /// CONSTRAINT: Must be this pool's `[POOL_SIGNER_NAMESPACE, pool]` PDA
+    #[account(
+        seeds = [POOL_SIGNER_NAMESPACE.as_bytes(), pool.key().as_ref()],
+        bump = pool.signer_bump,
+    )]
+    pub pool_signer: AccountInfo<'info>,
+
+    pub owner: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct EmitMetrics<'info> {
+    pub pool: Account<'info, PoolSafe>,
+}
+
+#[derive(Accounts)]
+pub struct CheckSolvency<'info> {
+    pub pool: Account<'info, PoolSafe>,
+
+    // SECURE: Must be this pool's own vault - any other account holding
+    // enough tokens would make the check pass
+    #[account(
+        token::authority = pool_signer,
+        constraint = pool_token.mint == pool.mint @ CustomError::MintMismatch,
+    )]
+    pub pool_token: Account<'info, TokenAccount>,
+
+    /// PDA that acts as authority for token account
+    /// CONSTRAINT: Must be this pool's `[POOL_SIGNER_NAMESPACE, pool]` PDA
+    #[account(
+        seeds = [POOL_SIGNER_NAMESPACE.as_bytes(), pool.key().as_ref()],
+        bump = pool.signer_bump,
+    )]
+    pub pool_signer: AccountInfo<'info>,
+}
+
+/// Pool-wide accounting
+///
+/// INVARIANT: `total_available <= total_deposited`. Deposits credit both
+/// totals and withdrawals debit both by the same amount (`try_deposit` /
+/// `try_withdraw`); an emergency withdrawal only ever lowers
+/// `total_available`. Fees are tracked separately in `total_fees`.
 #[account]
+#[derive(InitSpace)]
 pub struct PoolSafe {
     pub total_deposited: u64,
     pub total_available: u64,
-    pub locked: bool, // SECURE: Reentrancy guard
+    pub total_fees: u64, // Fees accrued to the pool, still held in its vault
+    pub locked: bool,    // SECURE: Reentrancy guard
+    pub lock_acquired_slot: u64, // Slot `locked` was last set at
+    pub signer_bump: u8, // SECURE: Bump of the pool_signer PDA
+    pub admin: Pubkey,   // May drain the pool, but only through the timelock
+    pub emergency_unlock_at: i64, // SECURE: 0 unless an emergency withdrawal is pending
+    pub mint: Pubkey,    // SECURE: The only mint deposited into or paid out of the pool
+}
+
+impl PoolSafe {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+
+    /// Runs `interaction` with the reentrancy guard held, stamped with `slot`
+    ///
+    /// `interaction` is handed the locked pool, as anything it calls back
+    /// into would see it. The guard is released whether `interaction`
+    /// succeeds or fails. A failed CPI aborts the whole transaction, which
+    /// would roll the guard back anyway; releasing it on every path means
+    /// the pool never depends on that, e.g. if an error is ever handled
+    /// instead of returned.
+    pub fn with_lock<T>(
+        &mut self,
+        slot: u64,
+        interaction: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        self.locked = true;
+        self.lock_acquired_slot = slot;
+        let result = interaction(self);
+        self.locked = false;
+        self.lock_acquired_slot = 0;
+        result
+    }
+
+    /// Applies the effects of a deposit: credits `user` and both pool totals
+    ///
+    /// The same amount lands in three counters. All of them are computed
+    /// before any is written, so they move together or not at all and an
+    /// overflow in any one fails the deposit the same way.
+    pub fn try_deposit(&mut self, user: &mut UserDeposit, amount: u64) -> Result<()> {
+        let (balance, total_deposited, total_available) = match (
+            user.balance.checked_add(amount),
+            self.total_deposited.checked_add(amount),
+            self.total_available.checked_add(amount),
+        ) {
+            (Some(balance), Some(deposited), Some(available)) => (balance, deposited, available),
+            _ => return err!(SecurityError::ArithmeticOverflow),
+        };
+
+        user.balance = balance;
+        self.total_deposited = total_deposited;
+        self.total_available = total_available;
+        Ok(())
+    }
+
+    /// Checks, effects and interaction of a withdrawal, in that order
+    ///
+    /// By the time `transferer` runs, `user` and both totals are already
+    /// debited and the guard is held. A transferer that calls back in - the
+    /// way a malicious token program would - finds the pool locked, and
+    /// even past the lock would find the balance already spent.
+    pub fn withdraw_with(
+        &mut self,
+        user: &mut UserDeposit,
+        amount: u64,
+        slot: u64,
+        transferer: &mut impl TokenTransferer,
+    ) -> Result<()> {
+        // PHASE 1: CHECKS - Verify preconditions, first failure wins
+        validate!(
+            // Reentrancy guard first: a re-entered call is refused before
+            // it reads any balance. The checks and checked math below are
+            // a second layer, refusing it again if the guard ever failed.
+            !self.locked => CustomError::PoolLocked,
+            user.balance >= amount => CustomError::InsufficientBalance,
+            self.total_available >= amount => CustomError::InsufficientPoolFunds,
+        )?;
+
+        // PHASE 2: EFFECTS - Update state FIRST (before external calls)
+        self.try_withdraw(user, amount)?;
+
+        // PHASE 3: INTERACTIONS - External calls happen LAST
+        // SECURE: The pool is locked for the transfer and unlocked after it,
+        // even if it fails
+        self.with_lock(slot, |pool| transferer.transfer(pool, user, amount))
+    }
+
+    /// Applies the effects of a withdrawal: debits `user` and both pool totals
+    ///
+    /// Only checked math, no CPI, so it can be exercised without a
+    /// validator. Nothing is written unless every counter has room.
+    ///
+    /// Callers check the balance first and report InsufficientBalance; if
+    /// that check is ever skipped (e.g. a re-entered call past the guard),
+    /// the subtraction here still refuses it with ArithmeticUnderflow.
+    pub fn try_withdraw(&mut self, user: &mut UserDeposit, amount: u64) -> Result<()> {
+        let balance = user.balance.checked_sub(amount)
+            .ok_or(SecurityError::ArithmeticUnderflow)?;
+        let total_deposited = self.total_deposited.checked_sub(amount)
+            .ok_or(SecurityError::ArithmeticUnderflow)?;
+        let total_available = self.total_available.checked_sub(amount)
+            .ok_or(SecurityError::ArithmeticUnderflow)?;
+
+        user.balance = balance;
+        self.total_deposited = total_deposited;
+        self.total_available = total_available;
+        Ok(())
+    }
+}
+
+/// Pays a withdrawal out of the pool vault
+///
+/// `withdraw_safe` moves tokens through this trait rather than calling the
+/// Token program directly, so the interaction can be swapped out in tests.
+/// It is handed the pool and the user's record as they stand mid-withdrawal,
+/// which is exactly what a re-entrant call would see.
+pub trait TokenTransferer {
+    fn transfer(&mut self, pool: &mut PoolSafe, user: &mut UserDeposit, amount: u64)
+        -> Result<()>;
+}
+
+/// The on-chain TokenTransferer: an SPL Token transfer signed by the pool
+pub struct CpiTransferer<'a, 'info> {
+    pub token_program: AccountInfo<'info>,
+    pub from: AccountInfo<'info>,
+    pub to: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub signer_seeds: &'a [&'a [&'a [u8]]],
+}
+
+impl TokenTransferer for CpiTransferer<'_, '_> {
+    fn transfer(&mut self, _: &mut PoolSafe, _: &mut UserDeposit, amount: u64) -> Result<()> {
+        token::transfer(
+            CpiContext::new_with_signer(
+                self.token_program.clone(),
+                Transfer {
+                    from: self.from.clone(),
+                    to: self.to.clone(),
+                    authority: self.authority.clone(),
+                },
+                self.signer_seeds,
+            ),
+            amount,
+        )
+    }
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct UserDeposit {
     pub owner: Pubkey,
+    pub pool: Pubkey, // SECURE: Record is bound to one pool
     pub balance: u64,
+    pub last_deposit_id: u64, // SECURE: Highest idempotency key processed
+    pub bump: u8,             // SECURE: Stored PDA bump
+}
+
+impl UserDeposit {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+
+    /// Address and bump of `owner`'s record in `pool`
+    pub fn address(pool: &Pubkey, owner: &Pubkey) -> Result<(Pubkey, u8)> {
+        pda::derive_pair(USER_DEPOSIT_NAMESPACE, pool, owner, &ID)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct AssetBalance {
+    pub mint: Pubkey,
+    pub total_deposited: u64, // Of this mint only
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MultiAssetPoolSafe {
+    pub admin: Pubkey,
+    pub signer_bump: u8,            // SECURE: Bump of the pool_signer PDA
+    #[max_len(MAX_POOL_MINTS)]
+    pub assets: Vec<AssetBalance>, // SECURE: One entry per supported mint
+}
+
+impl MultiAssetPoolSafe {
+    /// Bytes to allocate: discriminator + fields, at MAX_POOL_MINTS assets
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+
+    /// Position of `mint` in `assets`, or `UnsupportedMint`
+    pub fn asset_index(&self, mint: &Pubkey) -> Result<usize> {
+        self.assets
+            .iter()
+            .position(|asset| asset.mint == *mint)
+            .ok_or_else(|| error!(CustomError::UnsupportedMint))
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MultiAssetDeposit {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub balances: [u64; MAX_POOL_MINTS], // SECURE: Indexed like the pool's `assets`
+    pub bump: u8,
+}
+
+impl MultiAssetDeposit {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Result of `withdraw_safe`, returned as Borsh-encoded return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WithdrawReceipt {
+    pub new_balance: u64, // The user's balance after the withdrawal
+    pub withdrawn: u64,
+}
+
+/// Result of `check_solvency`, returned as Borsh-encoded return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SolvencyReport {
+    pub vault_balance: u64,   // Tokens held by the pool's vault
+    pub total_available: u64, // Tokens depositors can withdraw
+    pub surplus: u64,         // vault_balance - total_available, fees included
+}
+
+#[event]
+pub struct DepositEvent {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub new_total: u64, // The user's deposit balance afterwards
+}
+
+#[event]
+pub struct WithdrawEvent {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub new_total: u64, // The user's deposit balance afterwards
+}
+
+#[event]
+pub struct EmergencyWithdrawRequested {
+    pub admin: Pubkey,
+    pub unlock_at: i64, // Earliest unix timestamp the withdrawal can run
+}
+
+#[event]
+pub struct EmergencyWithdrawEvent {
+    pub admin: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolMetrics {
+    pub total_deposited: u64,
+    pub total_available: u64,
+    pub total_fees: u64,
+    pub locked: bool,
+    pub slot: u64, // Slot the snapshot was taken at
 }
 
 #[error_code]
@@ -220,15 +1127,39 @@ pub enum CustomError {
     #[msg("Insufficient pool funds")]
     InsufficientPoolFunds,
 
-    #[msg("Arithmetic underflow")]
-    ArithmeticUnderflow,
-
-    #[msg("Arithmetic overflow")]
-    ArithmeticOverflow,
-
     #[msg("Pool is locked (reentrancy protection)")]
     PoolLocked,
 
-    #[msg("Invalid amount")]
-    InvalidAmount,
+    #[msg("Deposit id was already used")]
+    DuplicateDepositId,
+
+    #[msg("Deposit record belongs to a different pool")]
+    PoolMismatch,
+
+    #[msg("Deposit record still holds data that closing would leave stale")]
+    StaleAccountData,
+
+    #[msg("Only the pool admin can do this")]
+    NotPoolAdmin,
+
+    #[msg("No emergency withdrawal has been requested or its timelock is still running")]
+    EmergencyTimelockActive,
+
+    #[msg("Pool is not locked, or its lock is not old enough to clear")]
+    LockNotStale,
+
+    #[msg("Mint is not supported by this pool")]
+    UnsupportedMint,
+
+    #[msg("Pool supports too many mints")]
+    TooManyMints,
+
+    #[msg("Mint is listed more than once")]
+    DuplicateMint,
+
+    #[msg("Token account does not hold the pool's mint")]
+    MintMismatch,
+
+    #[msg("Vault holds less than depositors can withdraw")]
+    Insolvent,
 }
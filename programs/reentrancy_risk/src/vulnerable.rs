@@ -21,6 +21,9 @@ use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
 declare_id!("55555555555555555555555555555555");
 
+/// Fee charged on `withdraw_with_fee_vulnerable`, in basis points
+pub const WITHDRAW_FEE_BPS: u64 = 30;
+
 #[program]
 pub mod reentrancy_risk {
     use super::*;
@@ -71,6 +74,126 @@ pub mod reentrancy_risk {
         Ok(())
     }
 
+    /// VULNERABLE: Withdraw with a fee, charged on the wrong side
+    pub fn withdraw_with_fee_vulnerable(
+        ctx: Context<WithdrawVulnerable>,
+        amount: u64,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let user = &mut ctx.accounts.user_deposit;
+
+        let fee = amount * WITHDRAW_FEE_BPS / 10_000;
+        let net = amount - fee;
+        require!(user.balance >= net, CustomError::InsufficientBalance);
+
+        // VULNERABILITY: The gross amount leaves the vault, but only the
+        // net is debited. The user keeps `fee` of balance they've already
+        // been paid, the fee is never retained, and every withdrawal leaks
+        // that much out of the other depositors' funds.
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token.to_account_info(),
+                    to: ctx.accounts.user_token.to_account_info(),
+                    authority: ctx.accounts.pool_signer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        user.balance -= net;
+        pool.total_deposited -= net;
+
+        msg!("Withdrew {} tokens ({} fee)", amount, fee);
+        Ok(())
+    }
+
+    /// VULNERABLE: Deposit without validating who controls which token account
+    pub fn deposit_vulnerable(
+        ctx: Context<DepositVulnerable>,
+        amount: u64,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let user = &mut ctx.accounts.user_deposit;
+
+        // VULNERABILITY: Nothing checks that `user_token` belongs to the user
+        // or that `pool_token` isn't controlled by them. If the user is also
+        // the pool token authority, they can pass the pool vault as the
+        // source and their own account as the "pool" - and get credited
+        // for moving pool funds to themselves.
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token.to_account_info(),
+                    to: ctx.accounts.pool_token.to_account_info(),
+                    authority: ctx.accounts.user_authority.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        // VULNERABILITY: Nothing checks the mint either. Every mint lands in
+        // the same balance and total, so 100 of a worthless token credits
+        // the same as 100 of a valuable one - and can be withdrawn from the
+        // valuable token's vault.
+        //
+        // VULNERABILITY: Each counter is updated on its own and wraps
+        // silently. A pool total near u64::MAX rolls over to a tiny number
+        // while the user's balance grows normally - the two no longer agree.
+        user.balance = user.balance.wrapping_add(amount);
+        pool.total_deposited = pool.total_deposited.wrapping_add(amount);
+
+        msg!("Deposited {} tokens", amount);
+        Ok(())
+    }
+
+    /// VULNERABLE: "Close" a deposit record by draining its lamports
+    pub fn close_deposit_vulnerable(
+        ctx: Context<CloseDepositVulnerable>,
+    ) -> Result<()> {
+        let record = ctx.accounts.user_deposit.to_account_info();
+        let owner = ctx.accounts.owner.to_account_info();
+
+        // VULNERABILITY: Only the lamports are moved. The data - including
+        // the UserDeposit discriminator and the old balance - stays, and the
+        // account stays owned by this program. The runtime only deletes it
+        // if it is still at zero lamports when the transaction ends, so
+        // refunding it in the same transaction revives the stale record.
+        let lamports = record.lamports();
+        **owner.try_borrow_mut_lamports()? += lamports;
+        **record.try_borrow_mut_lamports()? = 0;
+
+        msg!("Closed deposit record");
+        Ok(())
+    }
+
+    /// VULNERABLE: Admin drains the pool vault instantly
+    pub fn emergency_withdraw_vulnerable(
+        ctx: Context<EmergencyWithdrawVulnerable>,
+    ) -> Result<()> {
+        // VULNERABILITY: No timelock and no event. Depositors get no warning
+        // and no chance to exit first; a compromised admin key empties the
+        // vault in one transaction, and to indexers it looks like any other
+        // token transfer.
+        let amount = ctx.accounts.pool_token.amount;
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.pool_signer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        msg!("Emergency withdrew {} tokens", amount);
+        Ok(())
+    }
+
     /// VULNERABLE: Initialize pool without reentrancy guards
     pub fn initialize_pool_vulnerable(
         ctx: Context<InitializePoolVulnerable>,
@@ -78,6 +201,7 @@ pub mod reentrancy_risk {
         let pool = &mut ctx.accounts.pool;
         pool.total_deposited = 0;
         pool.total_available = 0;
+        pool.admin = ctx.accounts.authority.key();
         // NO reentrancy guard!
 
         msg!("Pool initialized");
@@ -90,9 +214,13 @@ pub struct WithdrawVulnerable<'info> {
     #[account(mut)]
     pub pool: Account<'info, Pool>,
 
+    // VULNERABILITY: Any UserDeposit is accepted - nothing ties it to the
+    // caller or this pool, so someone else's record can be substituted
     #[account(mut)]
     pub user_deposit: Account<'info, UserDeposit>,
 
+    // VULNERABILITY: The pool records no mint, so a balance deposited in
+    // any mint can be withdrawn from any vault this pool_signer controls
     #[account(mut)]
     pub pool_token: Account<'info, TokenAccount>,
 
@@ -106,9 +234,58 @@ pub struct WithdrawVulnerable<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct DepositVulnerable<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut)]
+    pub user_deposit: Account<'info, UserDeposit>,
+
+    // VULNERABILITY: No authority checks on either token account
+    #[account(mut)]
+    pub user_token: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub pool_token: Account<'info, TokenAccount>,
+
+    pub user_authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDepositVulnerable<'info> {
+    #[account(mut, has_one = owner)]
+    pub user_deposit: Account<'info, UserDeposit>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyWithdrawVulnerable<'info> {
+    #[account(has_one = admin)]
+    pub pool: Account<'info, Pool>,
+
+    pub admin: Signer<'info>,
+
+    #[account(mut)]
+    pub pool_token: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub destination: Account<'info, TokenAccount>,
+
+    /// PDA that acts as authority for token account
+    /// For this vulnerable example, it's not a true signer
+    pub pool_signer: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
 #[derive(Accounts)]
 pub struct InitializePoolVulnerable<'info> {
-    #[account(init, payer = authority, space = 8 + 8 + 8)]
+    #[account(init, payer = authority, space = Pool::SPACE)]
     pub pool: Account<'info, Pool>,
 
     #[account(mut)]
@@ -118,10 +295,17 @@ pub struct InitializePoolVulnerable<'info> {
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct Pool {
     pub total_deposited: u64,
     pub total_available: u64,
     // VULNERABILITY: No reentrancy guard like a locked flag
+    pub admin: Pubkey, // Can drain the pool at any moment
+}
+
+impl Pool {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
 }
 
 #[account]
@@ -140,4 +324,7 @@ pub enum CustomError {
 
     #[msg("Pool is locked")]
     PoolLocked,
+
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
 }
@@ -0,0 +1,161 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use security_utils::{pda, SecurityError};
+
+// ============================================================================
+// FIX: Initialize Exactly Once
+// ============================================================================
+//
+// WHAT'S FIXED:
+// The pool is created with `init`. Anchor's `init` asks the System Program
+// to create the account, and the System Program refuses an address that is
+// already in use - so a second `initialize_pool_safe` fails before the
+// handler runs, and the authority and books stay as they were.
+//
+// The pool also records `is_initialized`, and the handler refuses to run on
+// a pool that has it set. With `init` that check never fires; it is there so
+// the pool stays safe if it is ever created some other way (manually, or
+// with `init_if_needed` for client convenience).
+//
+// BEST PRACTICES:
+// 1. Create accounts with `init`, not `init_if_needed`
+// 2. If `init_if_needed` is unavoidable, guard the first-time writes with a
+//    flag stored on the account
+// 3. Never let an initializer overwrite an authority or a balance
+//
+// ============================================================================
+
+declare_id!("Reinit1111111111111111111111111111111111111");
+
+/// PDA namespace of the singleton Pool: `[POOL_NAMESPACE, program id]`
+pub const POOL_NAMESPACE: &str = "reinit_attack/pool";
+
+#[program]
+pub mod reinit_attack_secure {
+    use super::*;
+
+    /// SECURE: Create the `[POOL_NAMESPACE, program id]` PDA; fails if it already exists
+    pub fn initialize_pool_safe(ctx: Context<InitializePoolSafe>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+
+        // SECURE: Never re-run first-time setup on a live pool
+        require!(!pool.is_initialized, CustomError::AlreadyInitialized);
+
+        pool.is_initialized = true;
+        pool.authority = ctx.accounts.authority.key();
+        pool.total_deposited = 0;
+        pool.bump = ctx.bumps.pool;
+
+        msg!("Pool initialized with authority {}", pool.authority);
+        Ok(())
+    }
+
+    /// Deposit `amount` lamports into the pool
+    pub fn deposit_safe(ctx: Context<DepositSafe>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.total_deposited = pool
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+
+        msg!("Deposited {} lamports", amount);
+        Ok(())
+    }
+
+    /// Pay the authority every lamport above rent and `total_deposited`
+    pub fn sweep_excess_safe(ctx: Context<SweepExcessSafe>) -> Result<()> {
+        let pool = ctx.accounts.pool.to_account_info();
+        let authority = ctx.accounts.authority.to_account_info();
+
+        let reserved = Rent::get()?
+            .minimum_balance(pool.data_len())
+            .checked_add(ctx.accounts.pool.total_deposited)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+        let excess = pool.lamports().saturating_sub(reserved);
+
+        **pool.try_borrow_mut_lamports()? -= excess;
+        **authority.try_borrow_mut_lamports()? += excess;
+
+        msg!("Swept {} lamports", excess);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializePoolSafe<'info> {
+    // SECURE: `init` fails if the pool account already exists
+    #[account(
+        init,
+        payer = authority,
+        space = Pool::SPACE,
+        seeds = [POOL_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSafe<'info> {
+    #[account(mut, seeds = [POOL_NAMESPACE.as_bytes(), ID.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SweepExcessSafe<'info> {
+    #[account(
+        mut,
+        seeds = [POOL_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump = pool.bump,
+        has_one = authority
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub is_initialized: bool,
+    pub authority: Pubkey,
+    pub total_deposited: u64, // Lamports owed to depositors
+    pub bump: u8,
+}
+
+impl Pool {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+
+    /// Address and bump of the program's pool
+    pub fn address() -> Result<(Pubkey, u8)> {
+        pda::derive(POOL_NAMESPACE, &ID, &ID)
+    }
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Pool is already initialized")]
+    AlreadyInitialized,
+}
@@ -0,0 +1,155 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+// ============================================================================
+// VULNERABILITY: Account Reinitialization
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// The pool is created with `init_if_needed`, so `initialize_pool_vulnerable`
+// also succeeds on a pool that already exists. Anchor skips creating the
+// account, but the handler still runs its first-time setup:
+//
+//     authority       = whoever called it this time
+//     total_deposited = 0
+//
+// The depositors' lamports are still in the pool, but the books now say
+// nothing is owed to anyone. `sweep_excess_vulnerable` lets the authority
+// take whatever the pool holds beyond what it owes - which is now all of it.
+//
+// WHY IT'S UNSAFE:
+// - `init_if_needed` only skips account creation; the handler's writes run
+//   on every call
+// - Initialization assigns the authority, so anyone who can re-run it can
+//   take the pool over
+// - Resetting the accounting turns every deposit into "surplus"
+//
+// SEVERITY: CRITICAL
+// ============================================================================
+
+declare_id!("Reinit1111111111111111111111111111111111111");
+
+/// PDA namespace of the singleton Pool: `[POOL_NAMESPACE, program id]`
+pub const POOL_NAMESPACE: &str = "reinit_attack/pool";
+
+#[program]
+pub mod reinit_attack {
+    use super::*;
+
+    /// VULNERABLE: Create the `[POOL_NAMESPACE, program id]` PDA - or silently re-create it
+    pub fn initialize_pool_vulnerable(ctx: Context<InitializePoolVulnerable>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+
+        // VULNERABILITY: Nothing checks whether this pool was already set
+        // up, so a second call replaces the authority and zeroes the books
+        pool.authority = ctx.accounts.authority.key();
+        pool.total_deposited = 0;
+        pool.bump = ctx.bumps.pool;
+
+        msg!("Pool initialized with authority {}", pool.authority);
+        Ok(())
+    }
+
+    /// Deposit `amount` lamports into the pool
+    pub fn deposit_vulnerable(ctx: Context<DepositVulnerable>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.total_deposited = pool
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(CustomError::ArithmeticOverflow)?;
+
+        msg!("Deposited {} lamports", amount);
+        Ok(())
+    }
+
+    /// Pay the authority every lamport above rent and `total_deposited`
+    pub fn sweep_excess_vulnerable(ctx: Context<SweepExcessVulnerable>) -> Result<()> {
+        let pool = ctx.accounts.pool.to_account_info();
+        let authority = ctx.accounts.authority.to_account_info();
+
+        let reserved = Rent::get()?
+            .minimum_balance(pool.data_len())
+            .checked_add(ctx.accounts.pool.total_deposited)
+            .ok_or(CustomError::ArithmeticOverflow)?;
+        let excess = pool.lamports().saturating_sub(reserved);
+
+        **pool.try_borrow_mut_lamports()? -= excess;
+        **authority.try_borrow_mut_lamports()? += excess;
+
+        msg!("Swept {} lamports", excess);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializePoolVulnerable<'info> {
+    // VULNERABILITY: Succeeds whether or not the pool already exists
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = Pool::SPACE,
+        seeds = [POOL_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositVulnerable<'info> {
+    #[account(mut, seeds = [POOL_NAMESPACE.as_bytes(), ID.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SweepExcessVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [POOL_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump = pool.bump,
+        has_one = authority
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub authority: Pubkey,
+    pub total_deposited: u64, // Lamports owed to depositors
+    pub bump: u8,
+}
+
+impl Pool {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+}
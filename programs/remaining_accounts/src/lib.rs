@@ -0,0 +1,5 @@
+pub mod secure;
+pub mod vulnerable;
+
+#[cfg(not(feature = "no-entrypoint"))]
+pub use vulnerable::entry;
@@ -0,0 +1,5 @@
+pub mod secure;
+pub mod vulnerable;
+
+pub use secure::*;
+pub use vulnerable::*;
@@ -0,0 +1,197 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Validate Every remaining_account Before Using It
+// ============================================================================
+//
+// WHAT'S FIXED:
+// Each account in `remaining_accounts` gets the checks Anchor would have
+// run had it been a named `Account<'info, TokenAccount>` field, plus the
+// ones the rebate depends on:
+//
+//     Account::<TokenAccount>::try_from(info)  // Token program owns it
+//     token_account.mint == treasury.mint      // the rebate's mint
+//     token_account.owner == claimant          // the claimant's tokens
+//     no key listed twice                      // counted once
+//
+// The whole batch is validated before any lamports move, so one bad entry
+// rejects the claim outright. A forged account fails the owner check, and
+// a real account of another mint fails the mint check.
+//
+// BEST PRACTICES:
+// 1. Treat `remaining_accounts` as untrusted `AccountInfo`s, always
+// 2. Deserialize through `Account::try_from`, which checks the owner
+// 3. Compare every field the logic relies on - mint, owner, authority
+// 4. Reject duplicates when each account should only count once
+// 5. Validate the whole batch first, then act on it
+//
+// ============================================================================
+
+declare_id!("RemainingAccounts11111111111111111111111111");
+
+/// Lamports paid per token held
+pub const REBATE_LAMPORTS_PER_TOKEN: u64 = 1_000;
+
+/// PDA namespace of the singleton Treasury: `[TREASURY_NAMESPACE, program id]`
+pub const TREASURY_NAMESPACE: &str = "remaining_accounts/treasury";
+
+/// PDA namespace of a ClaimRecord: `[CLAIM_NAMESPACE, claimant]`
+pub const CLAIM_NAMESPACE: &str = "remaining_accounts/claim";
+
+#[program]
+pub mod remaining_accounts_secure {
+    use super::*;
+
+    /// Create the `[TREASURY_NAMESPACE, program id]` PDA, which pays rebates to holders of `mint`
+    pub fn initialize_treasury_safe(
+        ctx: Context<InitializeTreasurySafe>,
+        mint: Pubkey,
+    ) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.mint = mint;
+        treasury.bump = ctx.bumps.treasury;
+        Ok(())
+    }
+
+    /// SECURE: Pay the claimant's rebate on their token accounts of the
+    /// treasury's mint, passed in `remaining_accounts`
+    pub fn claim_rebate_safe<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimRebateSafe<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.claim.bump = ctx.bumps.claim;
+        let mint = ctx.accounts.treasury.mint;
+        let claimant_key = ctx.accounts.claimant.key();
+
+        // PHASE 1: Validate every account before paying for any
+        let mut total: u64 = 0;
+        for (i, info) in ctx.remaining_accounts.iter().enumerate() {
+            // SECURE: Only the Token program can have written this data
+            let token_account = Account::<TokenAccount>::try_from(info)?;
+
+            // SECURE: Tokens of the rebate's mint, held by the claimant
+            require_keys_eq!(token_account.mint, mint, CustomError::MintMismatch);
+            require_keys_eq!(
+                token_account.owner,
+                claimant_key,
+                CustomError::NotTokenOwner
+            );
+
+            // SECURE: The same account listed twice would be paid twice
+            require!(
+                ctx.remaining_accounts[..i]
+                    .iter()
+                    .all(|earlier| earlier.key != info.key),
+                SecurityError::DuplicateAccount
+            );
+
+            total = total
+                .checked_add(token_account.amount)
+                .ok_or(SecurityError::ArithmeticOverflow)?;
+        }
+
+        // PHASE 2: Pay for the validated total
+        let rebate = total
+            .checked_mul(REBATE_LAMPORTS_PER_TOKEN)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let claimant = ctx.accounts.claimant.to_account_info();
+        let treasury_lamports = treasury
+            .lamports()
+            .checked_sub(rebate)
+            .ok_or(CustomError::InsufficientFunds)?;
+        let claimant_lamports = claimant
+            .lamports()
+            .checked_add(rebate)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+        **treasury.try_borrow_mut_lamports()? = treasury_lamports;
+        **claimant.try_borrow_mut_lamports()? = claimant_lamports;
+
+        msg!(
+            "Paid {} lamports for {} accounts",
+            rebate,
+            ctx.remaining_accounts.len()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasurySafe<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Treasury::SPACE,
+        seeds = [TREASURY_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRebateSafe<'info> {
+    #[account(
+        mut,
+        seeds = [TREASURY_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    /// One claim per claimant: `init` fails if it already exists
+    #[account(
+        init,
+        payer = claimant,
+        space = ClaimRecord::SPACE,
+        seeds = [CLAIM_NAMESPACE.as_bytes(), claimant.key().as_ref()],
+        bump,
+    )]
+    pub claim: Account<'info, ClaimRecord>,
+
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    // remaining_accounts: the claimant's token accounts of `treasury.mint`,
+    // each validated in the handler
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    pub mint: Pubkey, // Holders of this mint are owed the rebate
+    pub bump: u8,
+}
+
+impl Treasury {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimRecord {
+    pub bump: u8,
+}
+
+impl ClaimRecord {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Token account does not hold the treasury's mint")]
+    MintMismatch,
+
+    #[msg("Token account does not belong to the claimant")]
+    NotTokenOwner,
+
+    #[msg("Treasury cannot cover the rebate")]
+    InsufficientFunds,
+}
@@ -0,0 +1,176 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+// ============================================================================
+// VULNERABILITY: Trusting Unvalidated remaining_accounts
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// The treasury pays holders of one mint a lamport rebate per token held.
+// A holder claims once, passing each of their token accounts in
+// `remaining_accounts`, and the batch pays out for every account in turn.
+//
+// Anchor validates the accounts named in the `#[derive(Accounts)]` struct,
+// but `remaining_accounts` arrive as raw `AccountInfo`s. The batch reads
+// each one's bytes as a token account and only checks the `owner` field:
+//
+//     1. Attacker creates an account owned by their own program, laid out
+//        like a token account: `owner` = attacker, `amount` = anything
+//     2. Passes it in `remaining_accounts`
+//     3. The batch pays the rebate on tokens that were never minted
+//
+// A real token account of some worthless mint the attacker controls works
+// just as well, since the mint is never compared either.
+//
+// WHY IT'S UNSAFE:
+// - Bytes that look like a token account prove nothing about who wrote them
+// - Only the Token program can write real token account data
+// - Nothing ties the accounts to the mint the rebate is for
+//
+// SEVERITY: CRITICAL
+// ============================================================================
+
+declare_id!("RemainingAccounts11111111111111111111111111");
+
+/// Lamports paid per token held
+pub const REBATE_LAMPORTS_PER_TOKEN: u64 = 1_000;
+
+/// PDA namespace of the singleton Treasury: `[TREASURY_NAMESPACE, program id]`
+pub const TREASURY_NAMESPACE: &str = "remaining_accounts/treasury";
+
+/// PDA namespace of a ClaimRecord: `[CLAIM_NAMESPACE, claimant]`
+pub const CLAIM_NAMESPACE: &str = "remaining_accounts/claim";
+
+#[program]
+pub mod remaining_accounts {
+    use super::*;
+
+    /// Create the `[TREASURY_NAMESPACE, program id]` PDA, which pays rebates to holders of `mint`
+    pub fn initialize_treasury_vulnerable(
+        ctx: Context<InitializeTreasuryVulnerable>,
+        mint: Pubkey,
+    ) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.mint = mint;
+        treasury.bump = ctx.bumps.treasury;
+        Ok(())
+    }
+
+    /// VULNERABLE: Pay the claimant's rebate on every token account in
+    /// `remaining_accounts`
+    pub fn claim_rebate_vulnerable<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimRebateVulnerable<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.claim.bump = ctx.bumps.claim;
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let claimant = ctx.accounts.claimant.to_account_info();
+
+        for info in ctx.remaining_accounts {
+            // VULNERABILITY: Reads the bytes without checking that the Token
+            // program owns the account, or which mint it holds
+            let token_account = TokenAccount::try_deserialize(&mut &info.data.borrow()[..])?;
+            require_keys_eq!(
+                token_account.owner,
+                claimant.key(),
+                CustomError::NotTokenOwner
+            );
+
+            let rebate = token_account
+                .amount
+                .checked_mul(REBATE_LAMPORTS_PER_TOKEN)
+                .ok_or(CustomError::ArithmeticOverflow)?;
+            let treasury_lamports = treasury
+                .lamports()
+                .checked_sub(rebate)
+                .ok_or(CustomError::InsufficientFunds)?;
+            let claimant_lamports = claimant
+                .lamports()
+                .checked_add(rebate)
+                .ok_or(CustomError::ArithmeticOverflow)?;
+            **treasury.try_borrow_mut_lamports()? = treasury_lamports;
+            **claimant.try_borrow_mut_lamports()? = claimant_lamports;
+
+            msg!("Paid {} lamports for {}", rebate, info.key());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasuryVulnerable<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Treasury::SPACE,
+        seeds = [TREASURY_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRebateVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [TREASURY_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    /// One claim per claimant: `init` fails if it already exists
+    #[account(
+        init,
+        payer = claimant,
+        space = ClaimRecord::SPACE,
+        seeds = [CLAIM_NAMESPACE.as_bytes(), claimant.key().as_ref()],
+        bump,
+    )]
+    pub claim: Account<'info, ClaimRecord>,
+
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    // remaining_accounts: the claimant's token accounts - taken on trust
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    pub mint: Pubkey, // Holders of this mint are owed the rebate
+    pub bump: u8,
+}
+
+impl Treasury {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimRecord {
+    pub bump: u8,
+}
+
+impl ClaimRecord {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+
+    #[msg("Treasury cannot cover the rebate")]
+    InsufficientFunds,
+
+    #[msg("Token account does not belong to the claimant")]
+    NotTokenOwner,
+}
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+use security_utils::safe_math::{safe_add, safe_sub};
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Round Fees Toward the Protocol
+// ============================================================================
+//
+// WHAT'S FIXED:
+// Every division has to round one way or the other; this version picks the
+// direction on purpose. A fee is owed TO the protocol, so it rounds UP:
+//
+//     product = amount * fee_bps
+//     fee     = product / 10_000, plus 1 if there is any remainder
+//
+//     amount = 1,   fee_bps = 1   ->  fee = 1
+//     amount = 333, fee_bps = 30  ->  fee = 1
+//
+// Splitting a withdrawal can now only cost the user more, never less.
+//
+// BEST PRACTICES:
+// 1. Decide the rounding direction for every division, and document it
+// 2. Round against the caller: up for what they owe, down for what they get
+// 3. Multiply before dividing, in a type wide enough for the product
+// 4. Handle the remainder explicitly instead of discarding it
+//
+// ============================================================================
+
+declare_id!("RoundingError111111111111111111111111111111");
+
+/// Basis points in 100%
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+#[program]
+pub mod rounding_error_secure {
+    use super::*;
+
+    /// SECURE: Withdraw `amount`, keeping a fee that rounds up
+    pub fn withdraw_secure(
+        ctx: Context<WithdrawSecure>,
+        amount: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let fee = fee_for(amount, vault.fee_bps)?;
+
+        vault.balance = safe_sub(vault.balance, amount)
+            .map_err(|_| error!(CustomError::InsufficientBalance))?;
+        vault.collected_fees = safe_add(vault.collected_fees, fee)?;
+
+        msg!("Withdrew {} ({} net, {} fee)", amount, amount - fee, fee);
+        Ok(())
+    }
+}
+
+/// SECURE: Fee owed on `amount` at `fee_bps`, rounded up
+///
+/// Never more than `amount` itself, since `fee_bps` is capped at 100%.
+pub fn fee_for(amount: u64, fee_bps: u16) -> Result<u64> {
+    require!(fee_bps as u64 <= BPS_DENOMINATOR, CustomError::InvalidFeeBps);
+    let denominator = BPS_DENOMINATOR as u128;
+
+    // SECURE: Multiply first, in u128 - no precision lost before dividing,
+    // and no overflow for any u64 amount
+    let product = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(SecurityError::ArithmeticOverflow)?;
+    let mut fee = product
+        .checked_div(denominator)
+        .ok_or(SecurityError::DivideByZero)?;
+
+    // SECURE: Any remainder is a fraction of a unit owed - round it up
+    if product % denominator != 0 {
+        fee += 1;
+    }
+    Ok(u64::try_from(fee).map_err(|_| SecurityError::ArithmeticOverflow)?)
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSecure<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+}
+
+#[account]
+pub struct Vault {
+    pub fee_bps: u16,
+    pub balance: u64,
+    pub collected_fees: u64,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Insufficient balance")]
+    InsufficientBalance,
+
+    #[msg("Fee must be at most 10000 basis points")]
+    InvalidFeeBps,
+}
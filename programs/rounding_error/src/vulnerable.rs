@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+// ============================================================================
+// VULNERABILITY: Fee Rounding Leakage
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// This program takes a basis-point fee on every withdrawal, computed as
+//
+//     fee = amount * fee_bps / 10_000
+//
+// Integer division truncates, so the fee always rounds DOWN - in the
+// user's favour. Whenever amount * fee_bps < 10_000 the fee is zero:
+//
+//     amount = 1,   fee_bps = 1   ->  fee = 0
+//     amount = 333, fee_bps = 30  ->  fee = 0   (9_990 / 10_000)
+//
+// WHY IT'S UNSAFE:
+// - Splitting one withdrawal into many small ones avoids the fee entirely
+// - Even when the fee isn't zero, every withdrawal leaks up to 1 unit
+// - On cheap chains, thousands of dust transactions cost less than the fee
+// - The protocol's books and its actual fee income drift apart
+//
+// SEVERITY: MEDIUM
+// ============================================================================
+
+declare_id!("RoundingError111111111111111111111111111111");
+
+#[program]
+pub mod rounding_error {
+    use super::*;
+
+    /// VULNERABLE: Withdraw `amount`, keeping a fee that rounds down
+    pub fn withdraw_vulnerable(
+        ctx: Context<WithdrawVulnerable>,
+        amount: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let fee = fee_for(amount, vault.fee_bps);
+
+        vault.balance = vault
+            .balance
+            .checked_sub(amount)
+            .ok_or(CustomError::InsufficientBalance)?;
+        vault.collected_fees = vault
+            .collected_fees
+            .checked_add(fee)
+            .ok_or(CustomError::ArithmeticOverflow)?;
+
+        msg!("Withdrew {} ({} net, {} fee)", amount, amount - fee, fee);
+        Ok(())
+    }
+}
+
+/// VULNERABLE: Fee owed on `amount` at `fee_bps`
+pub fn fee_for(amount: u64, fee_bps: u16) -> u64 {
+    // VULNERABILITY: Truncating division - the remainder is silently
+    // given to the user, and small amounts pay nothing at all
+    (amount as u128 * fee_bps as u128 / 10_000) as u64
+}
+
+#[derive(Accounts)]
+pub struct WithdrawVulnerable<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+}
+
+#[account]
+pub struct Vault {
+    pub fee_bps: u16,
+    pub balance: u64,
+    pub collected_fees: u64,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Insufficient balance")]
+    InsufficientBalance,
+
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+}
@@ -0,0 +1,130 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Fixed-Length Hashed Seeds
+// ============================================================================
+//
+// WHAT'S FIXED:
+// This version never puts user-controlled, variable-length bytes into seeds:
+// - Every user string is hashed to a fixed 32-byte seed
+// - The hash includes a per-account-type domain, so a name and a label
+//   with the same text still hash differently
+// - String length is validated up front
+//
+// BEST PRACTICES:
+// 1. Keep every seed fixed-length, or only let the LAST seed vary
+// 2. Hash user-supplied strings before using them as seeds
+// 3. Give each account type a distinct prefix that is not a prefix of another
+// 4. Bound input lengths explicitly (seeds are limited to 32 bytes each)
+//
+// ============================================================================
+
+declare_id!("88888888888888888888888888888888");
+
+pub const MAX_NAME_LEN: usize = 64;
+
+/// Hashes `value` under a domain into a fixed-length, collision-free seed
+pub fn hashed_seed(domain: &[u8], value: &str) -> [u8; 32] {
+    hashv(&[domain, value.as_bytes()]).to_bytes()
+}
+
+#[program]
+pub mod seed_collision_secure {
+    use super::*;
+
+    /// SECURE: Register a username at a PDA derived from its hash
+    pub fn register_safe(
+        ctx: Context<RegisterSafe>,
+        name: String,
+    ) -> Result<()> {
+        require!(name.len() <= MAX_NAME_LEN, SecurityError::InputTooLong);
+
+        let registration = &mut ctx.accounts.registration;
+        registration.owner = ctx.accounts.user.key();
+        registration.name = name;
+
+        msg!("Registered user: {}", registration.name);
+        Ok(())
+    }
+
+    /// SECURE: Create a vault at a PDA derived from the label's hash
+    pub fn create_vault_safe(
+        ctx: Context<CreateVaultSafe>,
+        label: String,
+    ) -> Result<()> {
+        require!(label.len() <= MAX_NAME_LEN, SecurityError::InputTooLong);
+
+        let vault = &mut ctx.accounts.vault;
+        vault.owner = ctx.accounts.user.key();
+        vault.label = label;
+
+        msg!("Created vault: {}", vault.label);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RegisterSafe<'info> {
+    /// SECURE: One fixed prefix + one fixed-length hash
+    #[account(
+        init,
+        payer = user,
+        space = Registration::SPACE,
+        seeds = [b"user", &hashed_seed(b"user", &name)],
+        bump,
+    )]
+    pub registration: Account<'info, Registration>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(label: String)]
+pub struct CreateVaultSafe<'info> {
+    /// SECURE: Different prefix AND different hash domain
+    #[account(
+        init,
+        payer = user,
+        space = Vault::SPACE,
+        seeds = [b"vault", &hashed_seed(b"vault", &label)],
+        bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Registration {
+    pub owner: Pubkey,
+    #[max_len(MAX_NAME_LEN)]
+    pub name: String,
+}
+
+impl Registration {
+    /// Bytes to allocate: discriminator + fields, at a MAX_NAME_LEN name
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub owner: Pubkey,
+    #[max_len(MAX_NAME_LEN)]
+    pub label: String,
+}
+
+impl Vault {
+    /// Bytes to allocate: discriminator + fields, at a MAX_NAME_LEN label
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
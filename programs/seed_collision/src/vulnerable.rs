@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+
+// ============================================================================
+// VULNERABILITY: PDA Seed Collision
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// This program derives PDAs directly from user-supplied strings of arbitrary
+// length. PDA derivation hashes the seeds concatenated together, so the
+// boundary between seeds is lost:
+//
+//     [b"user", b"_vaultTreasury"]  ==  [b"user_vault", b"Treasury"]
+//
+// Both produce the same bytes "user_vaultTreasury" and the same address.
+//
+// WHY IT'S UNSAFE:
+// - An attacker can register a name that squats on another account type's PDA
+// - The legitimate account can then never be created (denial of service)
+// - Or worse, code expecting one account type reads the other
+// - Names longer than 32 bytes abort derivation entirely
+//
+// SEVERITY: HIGH
+// ============================================================================
+
+declare_id!("88888888888888888888888888888888");
+
+#[program]
+pub mod seed_collision {
+    use super::*;
+
+    /// VULNERABLE: Register a username at a PDA derived from the raw name
+    pub fn register_unsafe(
+        ctx: Context<RegisterUnsafe>,
+        name: String,
+    ) -> Result<()> {
+        // VULNERABILITY: name goes straight into the seeds with no length
+        // limit and no separation from the "user" prefix
+        let registration = &mut ctx.accounts.registration;
+        registration.owner = ctx.accounts.user.key();
+        registration.name = name;
+
+        msg!("Registered user: {}", registration.name);
+        Ok(())
+    }
+
+    /// VULNERABLE: Create a vault at a PDA derived from the raw label
+    pub fn create_vault_unsafe(
+        ctx: Context<CreateVaultUnsafe>,
+        label: String,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.owner = ctx.accounts.user.key();
+        vault.label = label;
+
+        msg!("Created vault: {}", vault.label);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RegisterUnsafe<'info> {
+    /// VULNERABILITY: Seeds are "user" + name, concatenated
+    #[account(
+        init,
+        payer = user,
+        space = Registration::SPACE,
+        seeds = [b"user", name.as_bytes()],
+        bump,
+    )]
+    pub registration: Account<'info, Registration>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(label: String)]
+pub struct CreateVaultUnsafe<'info> {
+    /// VULNERABILITY: Seeds are "user_vault" + label, which overlaps
+    /// with "user" + "_vault..." above
+    #[account(
+        init,
+        payer = user,
+        space = Vault::SPACE,
+        seeds = [b"user_vault", label.as_bytes()],
+        bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Registration {
+    pub owner: Pubkey,
+    #[max_len(64)]
+    pub name: String,
+}
+
+impl Registration {
+    /// Bytes to allocate: discriminator + fields, at a 64-byte name
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub owner: Pubkey,
+    #[max_len(64)]
+    pub label: String,
+}
+
+impl Vault {
+    /// Bytes to allocate: discriminator + fields, at a 64-byte label
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
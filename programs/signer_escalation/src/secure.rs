@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+    pubkey,
+};
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Sign Only the Instruction the PDA Exists For
+// ============================================================================
+//
+// WHAT'S FIXED:
+// The treasury authority signs exactly one thing: the rewards program's
+// `claim_rewards` instruction. Before `invoke_signed` the program checks
+//
+//     target_program == REWARDS_PROGRAM_ID
+//     data[..8]      == CLAIM_REWARDS_DISCRIMINATOR
+//
+// A Transfer through the Token program, or any other rewards instruction,
+// fails before the PDA's signature is ever attached.
+//
+// BEST PRACTICES:
+// 1. Treat every `invoke_signed` as handing out the PDA's authority
+// 2. Allow-list both the program and the instruction, not just the program
+// 3. Prefer building the instruction yourself over forwarding caller data
+// 4. The callee still sees caller-chosen accounts - pin them too, or make
+//    sure the callee validates them
+//
+// ============================================================================
+
+declare_id!("SignerEsca1ation111111111111111111111111111");
+
+/// PDA namespace of the treasury authority: `[AUTHORITY_NAMESPACE, program id]`
+pub const AUTHORITY_NAMESPACE: &str = "signer_escalation/authority";
+
+/// The only program the treasury authority signs for
+pub const REWARDS_PROGRAM_ID: Pubkey = pubkey!("Rewards111111111111111111111111111111111111");
+
+/// Anchor discriminator of the rewards program's `claim_rewards`,
+/// `sha256("global:claim_rewards")[..8]`
+pub const CLAIM_REWARDS_DISCRIMINATOR: [u8; 8] = [4, 144, 132, 71, 116, 23, 151, 80];
+
+#[program]
+pub mod signer_escalation_secure {
+    use super::*;
+
+    /// SECURE: Claim rewards with the treasury authority signing
+    ///
+    /// Accounts after the named ones are forwarded to the rewards program;
+    /// wherever the authority PDA appears among them it is marked as a
+    /// signer.
+    pub fn call_as_treasury_safe<'info>(
+        ctx: Context<'_, '_, '_, 'info, CallAsTreasurySafe<'info>>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        // SECURE: Only the rewards program...
+        require!(
+            ctx.accounts.target_program.executable,
+            SecurityError::ProgramNotExecutable
+        );
+        require_keys_eq!(
+            ctx.accounts.target_program.key(),
+            REWARDS_PROGRAM_ID,
+            SecurityError::UntrustedProgram
+        );
+
+        // SECURE: ...and only its claim_rewards instruction
+        require!(
+            data.get(..8) == Some(&CLAIM_REWARDS_DISCRIMINATOR[..]),
+            CustomError::InstructionNotAllowed
+        );
+
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[AUTHORITY_NAMESPACE.as_bytes(), ID.as_ref(), &[ctx.bumps.treasury_authority]]];
+        signed_call(
+            &ctx.accounts.target_program,
+            &ctx.accounts.treasury_authority,
+            ctx.remaining_accounts,
+            data,
+            signer_seeds,
+        )?;
+
+        msg!("Claimed rewards for the treasury");
+        Ok(())
+    }
+}
+
+/// Invokes `target` with `accounts`, `authority` signing through `signer_seeds`
+fn signed_call<'info>(
+    target: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    data: Vec<u8>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let metas = accounts
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: account.key(),
+            is_signer: account.is_signer || account.key() == authority.key(),
+            is_writable: account.is_writable,
+        })
+        .collect();
+
+    let mut infos = accounts.to_vec();
+    infos.push(authority.clone());
+    infos.push(target.clone());
+    invoke_signed(
+        &Instruction {
+            program_id: target.key(),
+            accounts: metas,
+            data,
+        },
+        &infos,
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CallAsTreasurySafe<'info> {
+    /// CONSTRAINT: The program's `[AUTHORITY_NAMESPACE, program id]` PDA,
+    /// owner of the treasury
+    #[account(seeds = [AUTHORITY_NAMESPACE.as_bytes(), ID.as_ref()], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+
+    pub caller: Signer<'info>,
+
+    /// CONSTRAINT: Must be REWARDS_PROGRAM_ID, checked in the handler
+    pub target_program: AccountInfo<'info>,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Only claim_rewards may be signed by the treasury")]
+    InstructionNotAllowed,
+}
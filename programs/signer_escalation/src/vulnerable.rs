@@ -0,0 +1,108 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+};
+
+// ============================================================================
+// VULNERABILITY: Signer Escalation Through Arbitrary CPI
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// The program's `[AUTHORITY_NAMESPACE, program id]` PDA owns the treasury's
+// token accounts. To let anyone crank reward claims for the treasury,
+// `call_as_treasury_vulnerable` invokes a program with the PDA signing:
+//
+//     invoke_signed(target_program, caller's data, caller's accounts,
+//                   [[AUTHORITY_NAMESPACE, program id, bump]])
+//
+// Target, data and accounts all come from the caller. Point it at the SPL
+// Token program with a Transfer out of the treasury and the PDA signs that
+// instead - the treasury pays whoever asked.
+//
+// WHY IT'S UNSAFE:
+// - A PDA signature authorizes anything the PDA controls, in any program
+// - `invoke_signed` doesn't know what the call is for; the program must
+// - "Permissionless" plus "signed by the program" means anyone holds the key
+//
+// SEVERITY: CRITICAL
+// ============================================================================
+
+declare_id!("SignerEsca1ation111111111111111111111111111");
+
+/// PDA namespace of the treasury authority: `[AUTHORITY_NAMESPACE, program id]`
+pub const AUTHORITY_NAMESPACE: &str = "signer_escalation/authority";
+
+#[program]
+pub mod signer_escalation {
+    use super::*;
+
+    /// VULNERABLE: Call `target_program` with the treasury authority signing
+    ///
+    /// Accounts after the named ones are forwarded to the callee; wherever
+    /// the authority PDA appears among them it is marked as a signer.
+    pub fn call_as_treasury_vulnerable<'info>(
+        ctx: Context<'_, '_, '_, 'info, CallAsTreasuryVulnerable<'info>>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[AUTHORITY_NAMESPACE.as_bytes(), ID.as_ref(), &[ctx.bumps.treasury_authority]]];
+
+        // VULNERABILITY: Any program, any instruction - all signed by the PDA
+        signed_call(
+            &ctx.accounts.target_program,
+            &ctx.accounts.treasury_authority,
+            ctx.remaining_accounts,
+            data,
+            signer_seeds,
+        )?;
+
+        msg!("Called {} as the treasury", ctx.accounts.target_program.key());
+        Ok(())
+    }
+}
+
+/// Invokes `target` with `accounts`, `authority` signing through `signer_seeds`
+fn signed_call<'info>(
+    target: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    data: Vec<u8>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let metas = accounts
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: account.key(),
+            is_signer: account.is_signer || account.key() == authority.key(),
+            is_writable: account.is_writable,
+        })
+        .collect();
+
+    let mut infos = accounts.to_vec();
+    infos.push(authority.clone());
+    infos.push(target.clone());
+    invoke_signed(
+        &Instruction {
+            program_id: target.key(),
+            accounts: metas,
+            data,
+        },
+        &infos,
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CallAsTreasuryVulnerable<'info> {
+    /// CONSTRAINT: The program's `[AUTHORITY_NAMESPACE, program id]` PDA,
+    /// owner of the treasury
+    #[account(seeds = [AUTHORITY_NAMESPACE.as_bytes(), ID.as_ref()], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+
+    pub caller: Signer<'info>,
+
+    // VULNERABILITY: Any program at all
+    pub target_program: AccountInfo<'info>,
+}
@@ -0,0 +1,5 @@
+pub mod vulnerable;
+pub mod secure;
+
+#[cfg(not(feature = "no-entrypoint"))]
+pub use vulnerable::entry;
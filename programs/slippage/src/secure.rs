@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+use security_utils::safe_math::{safe_add, safe_sub};
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Let the Trader Set a Minimum Output
+// ============================================================================
+//
+// WHAT'S FIXED:
+// `swap_safe` takes `min_amount_out` alongside `amount_in`. The client
+// derives it from the price it was quoted, less the tolerance the trader
+// accepts, and the program refuses to fill below it:
+//
+//     require!(amount_out >= min_amount_out, SlippageExceeded)
+//
+// A front-run that moves the price past the tolerance makes the victim's
+// swap revert instead of filling at the worse price - and a sandwich whose
+// middle leg doesn't fill leaves the attacker holding their own trade.
+//
+// BEST PRACTICES:
+// 1. Every price-dependent instruction takes a bound the user signed for
+// 2. Check the bound against what is actually paid out, after any fees
+// 3. Let the client choose the tolerance; don't hard-code one on-chain
+// 4. Pair it with a deadline so a held-back transaction can't fill later
+//
+// ============================================================================
+
+declare_id!("S1ippage11111111111111111111111111111111111");
+
+#[program]
+pub mod slippage_secure {
+    use super::*;
+
+    /// SECURE: Sell `amount_in` of the input asset for at least
+    /// `min_amount_out` of the output asset
+    pub fn swap_safe(ctx: Context<SwapSafe>, amount_in: u64, min_amount_out: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let amount_out = quote(pool, amount_in)?;
+
+        // SECURE: Revert rather than fill below what the trader agreed to
+        require!(amount_out >= min_amount_out, CustomError::SlippageExceeded);
+
+        pool.reserve_in = safe_add(pool.reserve_in, amount_in)?;
+        pool.reserve_out = safe_sub(pool.reserve_out, amount_out)?;
+
+        msg!("Swapped {} in for {} out", amount_in, amount_out);
+        Ok(())
+    }
+}
+
+/// Output of selling `amount_in` into `pool`, at constant product
+///
+/// Never more than `reserve_out`, so paying it out can't underflow.
+/// Clients call this with the reserves they see to set `min_amount_out`.
+pub fn quote(pool: &Pool, amount_in: u64) -> Result<u64> {
+    let numerator = (pool.reserve_out as u128)
+        .checked_mul(amount_in as u128)
+        .ok_or(SecurityError::ArithmeticOverflow)?;
+    let denominator = (pool.reserve_in as u128)
+        .checked_add(amount_in as u128)
+        .ok_or(SecurityError::ArithmeticOverflow)?;
+    let amount_out = numerator
+        .checked_div(denominator)
+        .ok_or(SecurityError::DivideByZero)?;
+    Ok(u64::try_from(amount_out).map_err(|_| SecurityError::ArithmeticOverflow)?)
+}
+
+#[derive(Accounts)]
+pub struct SwapSafe<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Pool>,
+
+    pub trader: Signer<'info>,
+}
+
+#[account]
+pub struct Pool {
+    pub reserve_in: u64,  // Asset traders sell
+    pub reserve_out: u64, // Asset traders buy
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Swap output is below the trader's minimum")]
+    SlippageExceeded,
+}
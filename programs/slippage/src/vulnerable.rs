@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+
+// ============================================================================
+// VULNERABILITY: Swap Without a Minimum Output
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// `swap_vulnerable` sells `amount_in` into a constant-product pool at
+// whatever price the pool has when the transaction lands:
+//
+//     amount_out = reserve_out * amount_in / (reserve_in + amount_in)
+//
+// The trader commits to what they pay, never to what they get. Anyone who
+// sees the swap before it lands can sandwich it:
+//
+//     1. Front-run: sell into the pool first, pushing the price down
+//     2. The victim's swap fills at that worse price
+//     3. Back-run: buy back at the price the victim pushed down further,
+//        pocketing the difference
+//
+// WHY IT'S UNSAFE:
+// - The price the trader was quoted is not the price they execute at
+// - With no floor, any output - down to almost nothing - is a "success"
+// - Pending transactions are visible to whoever orders them into a block
+//
+// SEVERITY: HIGH
+// ============================================================================
+
+declare_id!("S1ippage11111111111111111111111111111111111");
+
+#[program]
+pub mod slippage {
+    use super::*;
+
+    /// VULNERABLE: Sell `amount_in` of the input asset for the output asset
+    pub fn swap_vulnerable(ctx: Context<SwapVulnerable>, amount_in: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let amount_out = quote(pool, amount_in)?;
+
+        // VULNERABILITY: Fills at any price - the trader never said how
+        // little they were willing to accept
+        pool.reserve_in = pool
+            .reserve_in
+            .checked_add(amount_in)
+            .ok_or(CustomError::ArithmeticOverflow)?;
+        pool.reserve_out -= amount_out;
+
+        msg!("Swapped {} in for {} out", amount_in, amount_out);
+        Ok(())
+    }
+}
+
+/// Output of selling `amount_in` into `pool`, at constant product
+///
+/// Never more than `reserve_out`, so paying it out can't underflow.
+pub fn quote(pool: &Pool, amount_in: u64) -> Result<u64> {
+    let numerator = (pool.reserve_out as u128)
+        .checked_mul(amount_in as u128)
+        .ok_or(CustomError::ArithmeticOverflow)?;
+    let denominator = (pool.reserve_in as u128)
+        .checked_add(amount_in as u128)
+        .ok_or(CustomError::ArithmeticOverflow)?;
+    let amount_out = numerator
+        .checked_div(denominator)
+        .ok_or(CustomError::EmptyPool)?;
+    Ok(u64::try_from(amount_out).map_err(|_| CustomError::ArithmeticOverflow)?)
+}
+
+#[derive(Accounts)]
+pub struct SwapVulnerable<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Pool>,
+
+    pub trader: Signer<'info>,
+}
+
+#[account]
+pub struct Pool {
+    pub reserve_in: u64,  // Asset traders sell
+    pub reserve_out: u64, // Asset traders buy
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+
+    #[msg("Pool has no liquidity")]
+    EmptyPool,
+}
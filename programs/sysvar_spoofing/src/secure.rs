@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Trusted Clock
+// ============================================================================
+//
+// WHAT'S FIXED:
+// The current time comes from `Clock::get()`, a syscall that returns the
+// runtime's own Clock. There is no clock account for the caller to choose,
+// so there is nothing to forge - a look-alike account passed alongside the
+// instruction is simply never read.
+//
+// BEST PRACTICES:
+// 1. Read sysvars with `Sysvar::get()` (`Clock::get()`, `Rent::get()`, ...)
+// 2. If an account must be taken, use `Sysvar<'info, Clock>`, which checks
+//    the address before deserializing
+// 3. Never decode a sysvar's bytes from an unchecked AccountInfo
+//
+// ============================================================================
+
+declare_id!("SysvarSpoofing11111111111111111111111111111");
+
+#[program]
+pub mod sysvar_spoofing_secure {
+    use super::*;
+
+    /// SECURE: Pay out the time lock once the runtime's clock allows
+    pub fn claim_secure(ctx: Context<ClaimSecure>) -> Result<()> {
+        // SECURE: The runtime's clock, not an account the caller picked
+        let now = Clock::get()?.unix_timestamp;
+        let time_lock = &mut ctx.accounts.time_lock;
+        require!(now >= time_lock.unlock_ts, CustomError::TimeLockActive);
+
+        let amount = time_lock.amount;
+        time_lock.amount = 0;
+
+        let from = time_lock.to_account_info();
+        let to = ctx.accounts.beneficiary.to_account_info();
+        **from.try_borrow_mut_lamports()? = from
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(CustomError::InsufficientFunds)?;
+        **to.try_borrow_mut_lamports()? = to
+            .lamports()
+            .checked_add(amount)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+
+        msg!("Claimed {} lamports at {}", amount, now);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimSecure<'info> {
+    #[account(mut, has_one = beneficiary @ SecurityError::Unauthorized)]
+    pub time_lock: Account<'info, TimeLock>,
+
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+}
+
+#[account]
+pub struct TimeLock {
+    pub beneficiary: Pubkey,
+    pub unlock_ts: i64,
+    pub amount: u64, // Lamports held on top of rent, paid out on claim
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Time lock has not expired")]
+    TimeLockActive,
+
+    #[msg("Time lock has insufficient funds")]
+    InsufficientFunds,
+}
@@ -0,0 +1,106 @@
+use anchor_lang::prelude::*;
+
+// ============================================================================
+// VULNERABILITY: Sysvar Spoofing
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// This program releases a time-locked payout once the current time passes
+// `unlock_ts`. It reads "the current time" from an account the caller
+// passes in as `clock`, decoding the bytes itself - without ever checking
+// that the account IS the Clock sysvar (SysvarC1ock11111111111111111111111111111111).
+//
+// Any account with the right layout will do. The attacker creates one
+// holding
+//
+//     [slot][epoch_start_timestamp][epoch][leader_schedule_epoch][unix_timestamp = unlock_ts]
+//
+// passes it as `clock`, and the time lock opens years early.
+//
+// WHY IT'S UNSAFE:
+// - Sysvars are ordinary accounts at well-known addresses; only the address
+//   makes the data trustworthy
+// - The Clock layout is public and fixed, so forging one is trivial
+// - Every time-based check (vesting, auctions, timelocks, interest) falls
+//
+// SEVERITY: HIGH
+// ============================================================================
+
+declare_id!("SysvarSpoofing11111111111111111111111111111");
+
+/// Offset of `unix_timestamp` in the Clock sysvar's data
+pub const CLOCK_UNIX_TIMESTAMP_OFFSET: usize = 32;
+
+#[program]
+pub mod sysvar_spoofing {
+    use super::*;
+
+    /// VULNERABLE: Pay out the time lock once the (claimed) time allows
+    pub fn claim_vulnerable(ctx: Context<ClaimVulnerable>) -> Result<()> {
+        // VULNERABILITY: Decodes whatever account was passed as the clock
+        let now = read_unix_timestamp(&ctx.accounts.clock)?;
+        let time_lock = &mut ctx.accounts.time_lock;
+        require!(now >= time_lock.unlock_ts, CustomError::TimeLockActive);
+
+        let amount = time_lock.amount;
+        time_lock.amount = 0;
+
+        let from = time_lock.to_account_info();
+        let to = ctx.accounts.beneficiary.to_account_info();
+        **from.try_borrow_mut_lamports()? = from
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(CustomError::InsufficientFunds)?;
+        **to.try_borrow_mut_lamports()? = to
+            .lamports()
+            .checked_add(amount)
+            .ok_or(CustomError::ArithmeticOverflow)?;
+
+        msg!("Claimed {} lamports at {}", amount, now);
+        Ok(())
+    }
+}
+
+/// VULNERABLE: Reads `unix_timestamp` out of a Clock-shaped account
+fn read_unix_timestamp(clock: &AccountInfo) -> Result<i64> {
+    // VULNERABILITY: No `clock.key() == sysvar::clock::ID` check
+    let data = clock.try_borrow_data()?;
+    let bytes = data
+        .get(CLOCK_UNIX_TIMESTAMP_OFFSET..CLOCK_UNIX_TIMESTAMP_OFFSET + 8)
+        .ok_or(CustomError::InvalidClock)?;
+    Ok(i64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+#[derive(Accounts)]
+pub struct ClaimVulnerable<'info> {
+    #[account(mut, has_one = beneficiary)]
+    pub time_lock: Account<'info, TimeLock>,
+
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+
+    /// VULNERABILITY: Address never checked - any Clock-shaped account passes
+    pub clock: AccountInfo<'info>,
+}
+
+#[account]
+pub struct TimeLock {
+    pub beneficiary: Pubkey,
+    pub unlock_ts: i64,
+    pub amount: u64, // Lamports held on top of rent, paid out on claim
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Time lock has not expired")]
+    TimeLockActive,
+
+    #[msg("Clock account is too short")]
+    InvalidClock,
+
+    #[msg("Time lock has insufficient funds")]
+    InsufficientFunds,
+
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+}
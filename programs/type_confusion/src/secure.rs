@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Discriminator-Checked Accounts
+// ============================================================================
+//
+// WHAT'S FIXED:
+// This version takes the config as `Account<'info, AdminConfig>`. Before the
+// handler runs, Anchor checks:
+// - The account is owned by this program
+// - Its first 8 bytes are AdminConfig's discriminator
+//   (the first 8 bytes of sha256("account:AdminConfig"))
+//
+// A UserAccount fails the second check with AccountDiscriminatorMismatch,
+// however closely its fields line up.
+//
+// BEST PRACTICES:
+// 1. Use `Account<'info, T>` (or `AccountLoader<'info, T>`) for program state
+// 2. If you must deserialize by hand, use `T::try_deserialize`, which
+//    checks the discriminator, never `T::deserialize` on `data[8..]`
+// 3. Don't rely on the owner check alone - a program owns all its types
+// 4. Relate accounts with `has_one` instead of comparing fields by hand
+//
+// ============================================================================
+
+declare_id!("77777777777777777777777777777777");
+
+#[program]
+pub mod type_confusion_secure {
+    use super::*;
+
+    /// SECURE: Admin credits a user's balance
+    pub fn credit_user_safe(
+        ctx: Context<CreditUserSafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        require!(amount <= config.max_credit, CustomError::CreditTooLarge);
+
+        let user = &mut ctx.accounts.user;
+        user.balance = user
+            .balance
+            .checked_add(amount)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+
+        msg!("Credited {} to {}", amount, user.authority);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreditUserSafe<'info> {
+    /// SECURE: Owner and AdminConfig discriminator checked by Anchor
+    #[account(has_one = admin @ SecurityError::Unauthorized)]
+    pub config: Account<'info, AdminConfig>,
+
+    pub admin: Signer<'info>,
+
+    #[account(mut)]
+    pub user: Account<'info, UserAccount>,
+}
+
+#[account]
+pub struct UserAccount {
+    pub authority: Pubkey,
+    pub balance: u64,
+}
+
+/// Same field layout as UserAccount; only the discriminator differs
+#[account]
+pub struct AdminConfig {
+    pub admin: Pubkey,
+    pub max_credit: u64,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Credit exceeds the configured maximum")]
+    CreditTooLarge,
+}
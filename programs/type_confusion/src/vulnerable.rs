@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+
+// ============================================================================
+// VULNERABILITY: Account Type Confusion
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// This program reads its admin config from a raw AccountInfo and
+// deserializes it by hand, skipping the 8-byte discriminator without
+// checking it. The discriminator is the only thing that tells one
+// `#[account]` type from another owned by the same program.
+//
+// UserAccount and AdminConfig share a layout (a Pubkey, then a u64):
+//
+//     UserAccount:  [disc "UserAccount"] [authority] [balance]
+//     AdminConfig:  [disc "AdminConfig"] [admin]     [max_credit]
+//
+// With the discriminator ignored, any user's own UserAccount parses as an
+// AdminConfig whose `admin` is that user.
+//
+// WHY IT'S UNSAFE:
+// - The owner check passes: both accounts belong to this program
+// - The admin check passes: the attacker is the "admin" of their own account
+// - The attacker's balance doubles as the config's credit limit
+// - Any user can credit any account with admin privileges
+//
+// SEVERITY: CRITICAL
+// ============================================================================
+
+declare_id!("77777777777777777777777777777777");
+
+#[program]
+pub mod type_confusion {
+    use super::*;
+
+    /// VULNERABLE: Admin credits a user's balance
+    pub fn credit_user_unsafe(
+        ctx: Context<CreditUserUnsafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let config_info = &ctx.accounts.config;
+        require_keys_eq!(*config_info.owner, crate::ID, CustomError::WrongAccountOwner);
+
+        // VULNERABILITY: The first 8 bytes are skipped, not compared against
+        // AdminConfig's discriminator - any account of the same layout parses
+        let data = config_info.try_borrow_data()?;
+        let config = AdminConfig::deserialize(&mut &data[8..])?;
+
+        require_keys_eq!(config.admin, ctx.accounts.admin.key(), CustomError::Unauthorized);
+        require!(amount <= config.max_credit, CustomError::CreditTooLarge);
+
+        let user = &mut ctx.accounts.user;
+        user.balance = user
+            .balance
+            .checked_add(amount)
+            .ok_or(CustomError::ArithmeticOverflow)?;
+
+        msg!("Credited {} to {}", amount, user.authority);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreditUserUnsafe<'info> {
+    /// VULNERABILITY: Raw account - its type is never checked
+    pub config: AccountInfo<'info>,
+
+    pub admin: Signer<'info>,
+
+    #[account(mut)]
+    pub user: Account<'info, UserAccount>,
+}
+
+#[account]
+pub struct UserAccount {
+    pub authority: Pubkey,
+    pub balance: u64,
+}
+
+/// Same field layout as UserAccount; only the discriminator differs
+#[account]
+pub struct AdminConfig {
+    pub admin: Pubkey,
+    pub max_credit: u64,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Account is owned by the wrong program")]
+    WrongAccountOwner,
+
+    #[msg("Signer is not the config admin")]
+    Unauthorized,
+
+    #[msg("Credit exceeds the configured maximum")]
+    CreditTooLarge,
+
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+}
@@ -6,12 +6,12 @@ use anchor_lang::prelude::*;
 //
 // WHAT'S FIXED:
 // This version implements checked arithmetic with overflow protection:
-// - Uses checked_add/checked_sub that return Option
+// - Every add, sub and mul goes through security_utils::safe_math
 // - Validates results before using them
-// - Fails safely on arithmetic errors
+// - Fails safely on arithmetic errors, with the shared SecurityError codes
 //
 // BEST PRACTICES:
-// 1. Always use checked arithmetic (checked_add, checked_sub, checked_mul)
+// 1. Always use checked arithmetic (safe_add, safe_sub, safe_mul, safe_mul_div)
 // 2. Use require! to validate arithmetic results
 // 3. Consider using i128 for intermediate calculations
 // 4. Document assumptions about range of values
@@ -19,59 +19,153 @@ use anchor_lang::prelude::*;
 // ============================================================================
 
 use anchor_lang::prelude::*;
+use security_utils::safe_math::{safe_add, safe_mul, safe_mul_div, safe_sub};
+use security_utils::{pda, SecurityError};
+
+// The checked-math core, re-exported for clients computing expected values
+pub use security_math as math;
 
 declare_id!("33333333333333333333333333333333");
 
+/// PDA namespace of a UserBalance record: `[USER_BALANCE_NAMESPACE, pool, owner]`
+pub const USER_BALANCE_NAMESPACE: &str = "unsafe_arithmetic/user_balance";
+
+/// PDA namespace of the singleton Config: `[CONFIG_NAMESPACE, program id]`
+pub const CONFIG_NAMESPACE: &str = "unsafe_arithmetic/config";
+
 #[program]
 pub mod unsafe_arithmetic_secure {
     use super::*;
 
-    /// SECURE: Deposit with checked arithmetic
+    /// SECURE: Create the config account, recording the caller as admin
+    pub fn init_config(
+        ctx: Context<InitConfig>,
+        params: ConfigParams,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.bump = ctx.bumps.config;
+        config.apply(params);
+
+        msg!("Config initialized with admin: {}", config.admin);
+        Ok(())
+    }
+
+    /// SECURE: Update parameters, only callable by the stored admin
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        params: ConfigParams,
+    ) -> Result<()> {
+        // Signer + has_one = admin have already been checked by Anchor
+        ctx.accounts.config.apply(params);
+
+        msg!("Config updated");
+        Ok(())
+    }
+
+    /// SECURE: Open a balance record for the signer in this pool
+    ///
+    /// The record lives at the PDA `[USER_BALANCE_NAMESPACE, pool, owner]`, so
+    /// each owner has exactly one per pool. Clients find it with
+    /// `UserBalance::address`.
+    pub fn open_user_balance_safe(
+        ctx: Context<OpenUserBalanceSafe>,
+    ) -> Result<()> {
+        let user = &mut ctx.accounts.user_balance;
+        user.owner = ctx.accounts.owner.key();
+        user.pool = ctx.accounts.pool.key();
+        user.balance = 0;
+        user.bump = ctx.bumps.user_balance;
+
+        msg!("Opened balance record for {}", user.owner);
+        Ok(())
+    }
+
+    /// SECURE: Deposit with checked arithmetic, credited to the signer
     pub fn deposit_safe(
         ctx: Context<DepositSafe>,
         amount: u64,
     ) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let config = &ctx.accounts.config;
         let account = &mut ctx.accounts.pool;
 
-        // SECURE: Use checked_add which returns Option
-        // This prevents silent wrapping on overflow
-        account.total_deposited = account
-            .total_deposited
-            .checked_add(amount)
-            .ok_or(CustomError::ArithmeticOverflow)?;
-
-        // SECURE: Checked multiplication for reward calculation
-        let reward_rate = 100u64;
-        let rewards = amount
-            .checked_mul(reward_rate)
-            .ok_or(CustomError::ArithmeticOverflow)?;
-        
-        account.total_rewards = account
-            .total_rewards
-            .checked_add(rewards)
-            .ok_or(CustomError::ArithmeticOverflow)?;
+        // SECURE: Parameters come from the admin-controlled config,
+        // not from constants baked into the binary
+        require!(!config.paused, CustomError::ProtocolPaused);
+        require!(amount <= config.max_deposit, CustomError::DepositTooLarge);
+
+        apply_deposit(account, amount, config.reward_rate)?;
+
+        let user = &mut ctx.accounts.user_balance;
+        user.balance = safe_add(user.balance, amount)?;
+
+        emit!(DepositEvent {
+            user: owner,
+            amount,
+            new_total: user.balance,
+        });
 
         msg!("Deposited: {}, Total: {}", amount, account.total_deposited);
         Ok(())
     }
 
-    /// SECURE: Withdrawal with checked arithmetic
+    /// SECURE: Withdrawal with checked arithmetic, limited to the signer's
+    /// credited balance
     pub fn withdraw_safe(
         ctx: Context<WithdrawSafe>,
         amount: u64,
     ) -> Result<()> {
         let account = &mut ctx.accounts.pool;
+        let user = &mut ctx.accounts.user_balance;
+
+        // SECURE: The pool holding enough is not the same as the caller
+        // being owed it - check the caller's own entitlement first
+        require!(user.balance >= amount, CustomError::InsufficientBalance);
 
-        // SECURE: Use checked_sub to prevent underflow
-        account.total_available = account
-            .total_available
-            .checked_sub(amount)
-            .ok_or(CustomError::ArithmeticUnderflow)?;
+        apply_withdraw(account, amount)?;
+        user.balance = safe_sub(user.balance, amount)?;
+
+        emit!(WithdrawEvent {
+            user: user.owner,
+            amount,
+            new_total: user.balance,
+        });
 
         msg!("Withdrew: {}, Remaining: {}", amount, account.total_available);
         Ok(())
     }
 
+    /// SECURE: Best-effort withdrawal, clamped to both the signer's
+    /// credited balance and what the pool has available
+    pub fn withdraw_up_to_safe(
+        ctx: Context<WithdrawSafe>,
+        requested: u64,
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.pool;
+        let user = &mut ctx.accounts.user_balance;
+
+        // SECURE: Clamp first, so neither subtraction below can underflow.
+        // What the pool holds is not what the caller is owed, so the
+        // caller's own balance caps the withdrawal too.
+        let withdrawn = requested.min(user.balance).min(account.total_available);
+
+        apply_withdraw(account, withdrawn)?;
+        user.balance = safe_sub(user.balance, withdrawn)?;
+
+        // Tell the caller they received less than they asked for
+        if withdrawn < requested {
+            emit!(PartialWithdrawEvent {
+                requested,
+                withdrawn,
+                remaining: account.total_available,
+            });
+        }
+
+        msg!("Withdrew: {}, Remaining: {}", withdrawn, account.total_available);
+        Ok(())
+    }
+
     /// SECURE: Mint tokens with overflow protection
     pub fn mint_interest_safe(
         ctx: Context<MintInterestSafe>,
@@ -80,42 +174,217 @@ pub mod unsafe_arithmetic_secure {
     ) -> Result<()> {
         let account = &mut ctx.accounts.pool;
 
+        require!(!ctx.accounts.config.paused, CustomError::ProtocolPaused);
+
         // Validate inputs first
-        require!(interest_rate <= 10000, CustomError::InvalidInterestRate); // max 100%
-
-        // SECURE: Use checked_mul to detect overflow early
-        let interest = base_amount
-            .checked_mul(interest_rate)
-            .ok_or(CustomError::ArithmeticOverflow)?
-            .checked_div(100)
-            .ok_or(CustomError::ArithmeticOverflow)?;
-        
-        account.total_minted = account
-            .total_minted
-            .checked_add(interest)
-            .ok_or(CustomError::ArithmeticOverflow)?;
+        // SECURE: The cap is admin-configured, not baked into the binary
+        require!(
+            interest_rate <= ctx.accounts.config.max_interest_bps,
+            CustomError::InvalidInterestRate
+        );
+
+        // SECURE: Checked multiply detects overflow early
+        let interest =
+            math::percent_of(base_amount, interest_rate).map_err(SecurityError::from)?;
+
+        account.total_minted = safe_add(account.total_minted, interest)?;
 
         msg!("Minted interest: {}", interest);
         Ok(())
     }
+
+    /// SECURE: Mint interest at `interest_rate / divisor`, with a zero
+    /// divisor rejected as `DivideByZero`
+    pub fn mint_interest_with_divisor_safe(
+        ctx: Context<MintInterestSafe>,
+        base_amount: u64,
+        interest_rate: u64,
+        divisor: u64,
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.pool;
+
+        require!(!ctx.accounts.config.paused, CustomError::ProtocolPaused);
+
+        // SECURE: The divisor is checked before anything divides by it
+        let interest = interest_with_divisor(base_amount, interest_rate, divisor)?;
+
+        // SECURE: Cap the effective rate, not just the numerator - a small
+        // divisor would otherwise scale the interest past the cap
+        require!(
+            interest_rate as u128 * 100
+                <= ctx.accounts.config.max_interest_bps as u128 * divisor as u128,
+            CustomError::InvalidInterestRate
+        );
+
+        account.total_minted = safe_add(account.total_minted, interest)?;
+
+        msg!("Minted interest: {}", interest);
+        Ok(())
+    }
+
+    /// SECURE: Trade at the oracle price, with a deviation circuit breaker
+    pub fn trade_safe(
+        ctx: Context<TradeSafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let price = ctx.accounts.price_feed.price;
+
+        // SECURE: Refuse to trade if the price moved too far from the
+        // reference - a sudden jump is more likely manipulation than market
+        ctx.accounts.price_guard.check(price)?;
+
+        let cost = safe_mul(amount, price)?;
+
+        msg!("Traded {} at price {} (cost {})", amount, price, cost);
+        Ok(())
+    }
+}
+
+/// SECURE: Pool bookkeeping for deposit_safe
+///
+/// Every new value is computed before any is stored, so on error the pool
+/// is left exactly as it was.
+pub fn apply_deposit(pool: &mut Pool, amount: u64, reward_rate: u64) -> Result<()> {
+    // SECURE: Checked add - ArithmeticOverflow instead of silent wrapping
+    let total_deposited = safe_add(pool.total_deposited, amount)?;
+
+    // SECURE: Checked multiplication for reward calculation
+    let rewards = safe_mul(amount, reward_rate)?;
+    let total_rewards = safe_add(pool.total_rewards, rewards)?;
+
+    pool.total_deposited = total_deposited;
+    pool.total_rewards = total_rewards;
+    Ok(())
+}
+
+/// SECURE: Pool bookkeeping for withdraw_safe
+pub fn apply_withdraw(pool: &mut Pool, amount: u64) -> Result<()> {
+    // SECURE: Checked sub - ArithmeticUnderflow instead of wrapping
+    pool.total_available = safe_sub(pool.total_available, amount)?;
+    Ok(())
+}
+
+/// SECURE: Interest on `base_amount` at `interest_rate / divisor`, rounded down
+///
+/// A zero `divisor` is `DivideByZero`, never a panic.
+pub fn interest_with_divisor(base_amount: u64, interest_rate: u64, divisor: u64) -> Result<u64> {
+    safe_mul_div(base_amount, interest_rate, divisor)
+}
+
+/// SECURE: Percentage change from `old` to `new` in basis points
+///
+/// See `math::bps_change`; a zero `old` is `DivideByZero`.
+pub fn bps_change(old: u64, new: u64) -> Result<i128> {
+    Ok(math::bps_change(old, new).map_err(SecurityError::from)?)
+}
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    /// Singleton config PDA
+    #[account(
+        init,
+        payer = admin,
+        space = Config::SPACE,
+        seeds = [CONFIG_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Becomes the admin allowed to update the config
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    /// CONSTRAINT: Only the stored admin may update
+    #[account(
+        mut,
+        seeds = [CONFIG_NAMESPACE.as_bytes(), ID.as_ref()],
+        bump = config.bump,
+        has_one = admin @ CustomError::NotAdmin,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// SECURE: Must sign AND match config.admin
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenUserBalanceSafe<'info> {
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = UserBalance::SPACE,
+        seeds = [USER_BALANCE_NAMESPACE.as_bytes(), pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub user_balance: Account<'info, UserBalance>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct DepositSafe<'info> {
     #[account(mut)]
     pub pool: Account<'info, Pool>,
+
+    #[account(seeds = [CONFIG_NAMESPACE.as_bytes(), ID.as_ref()], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CONSTRAINT: The signer's own record in this pool
+    #[account(
+        mut,
+        seeds = [USER_BALANCE_NAMESPACE.as_bytes(), pool.key().as_ref(), owner.key().as_ref()],
+        bump = user_balance.bump,
+    )]
+    pub user_balance: Account<'info, UserBalance>,
+
+    pub owner: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct WithdrawSafe<'info> {
     #[account(mut)]
     pub pool: Account<'info, Pool>,
+
+    // SECURE: The record must be this owner's PDA in this pool.
+    // has_one re-checks the stored fields in case the seeds ever change.
+    #[account(
+        mut,
+        seeds = [USER_BALANCE_NAMESPACE.as_bytes(), pool.key().as_ref(), owner.key().as_ref()],
+        bump = user_balance.bump,
+        has_one = owner @ SecurityError::Unauthorized,
+        has_one = pool,
+    )]
+    pub user_balance: Account<'info, UserBalance>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TradeSafe<'info> {
+    pub price_guard: Account<'info, PriceGuard>,
+
+    pub price_feed: Account<'info, PriceFeed>,
+
+    pub trader: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct MintInterestSafe<'info> {
     #[account(mut)]
     pub pool: Account<'info, Pool>,
+
+    #[account(seeds = [CONFIG_NAMESPACE.as_bytes(), ID.as_ref()], bump = config.bump)]
+    pub config: Account<'info, Config>,
 }
 
 #[account]
@@ -126,14 +395,127 @@ pub struct Pool {
     pub total_minted: u64,
 }
 
+/// What one owner is entitled to withdraw from one pool
+#[account]
+#[derive(InitSpace)]
+pub struct UserBalance {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub balance: u64, // Credited by deposit_safe, debited by withdraw_safe
+    pub bump: u8,
+}
+
+impl UserBalance {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+
+    /// Address and bump of `owner`'s record in `pool`
+    pub fn address(pool: &Pubkey, owner: &Pubkey) -> Result<(Pubkey, u8)> {
+        pda::derive_pair(USER_BALANCE_NAMESPACE, pool, owner, &ID)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub max_deposit: u64,
+    pub reward_rate: u64,
+    pub max_interest_bps: u64,
+    pub bump: u8,
+}
+
+impl Config {
+    /// Bytes to allocate: discriminator + fields
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+
+    /// Address and bump of the program's config
+    pub fn address() -> Result<(Pubkey, u8)> {
+        pda::derive(CONFIG_NAMESPACE, &ID, &ID)
+    }
+
+    /// Stores a new set of parameters
+    pub fn apply(&mut self, params: ConfigParams) {
+        self.paused = params.paused;
+        self.max_deposit = params.max_deposit;
+        self.reward_rate = params.reward_rate;
+        self.max_interest_bps = params.max_interest_bps;
+    }
+}
+
+/// Latest price published by the oracle
+#[account]
+pub struct PriceFeed {
+    pub price: u64,
+}
+
+/// Circuit breaker bounds for PriceFeed prices
+#[account]
+pub struct PriceGuard {
+    pub reference_price: u64,
+    pub max_deviation_bps: u16,
+}
+
+impl PriceGuard {
+    /// Rejects `price` if it is more than max_deviation_bps away from the reference
+    pub fn check(&self, price: u64) -> Result<()> {
+        let deviation = bps_change(self.reference_price, price)?;
+        require!(
+            deviation.unsigned_abs() <= self.max_deviation_bps as u128,
+            CustomError::PriceDeviationTooHigh
+        );
+        Ok(())
+    }
+}
+
+/// Admin-settable parameters, passed to init_config / update_config
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConfigParams {
+    pub paused: bool,
+    pub max_deposit: u64,
+    pub reward_rate: u64,
+    pub max_interest_bps: u64,
+}
+
+#[event]
+pub struct DepositEvent {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub new_total: u64, // The user's credited balance afterwards
+}
+
+#[event]
+pub struct WithdrawEvent {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub new_total: u64, // The user's credited balance afterwards
+}
+
+#[event]
+pub struct PartialWithdrawEvent {
+    pub requested: u64,
+    pub withdrawn: u64,
+    pub remaining: u64,
+}
+
 #[error_code]
 pub enum CustomError {
-    #[msg("Arithmetic overflow detected")]
-    ArithmeticOverflow,
-    
-    #[msg("Arithmetic underflow detected")]
-    ArithmeticUnderflow,
-    
     #[msg("Invalid interest rate")]
     InvalidInterestRate,
+
+    #[msg("Only the config admin can do this")]
+    NotAdmin,
+
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+
+    #[msg("Deposit exceeds the configured maximum")]
+    DepositTooLarge,
+
+    #[msg("Oracle price deviates too far from the reference price")]
+    PriceDeviationTooHigh,
+
+    #[msg("Withdrawal exceeds the caller's credited balance")]
+    InsufficientBalance,
 }
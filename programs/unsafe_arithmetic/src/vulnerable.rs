@@ -30,17 +30,7 @@ pub mod unsafe_arithmetic {
         amount: u64,
     ) -> Result<()> {
         let account = &mut ctx.accounts.pool;
-
-        // VULNERABILITY: Using wrapping add without overflow checks
-        // If pool.total_deposited is u64::MAX and someone deposits 1,
-        // it wraps to 0 instead of overflowing!
-        account.total_deposited = account.total_deposited.wrapping_add(amount);
-
-        // VULNERABILITY: Using unchecked multiplication
-        // If we calculate rewards without checking overflow:
-        let reward_rate = 100u64; // 100 basis points
-        let rewards = amount.wrapping_mul(reward_rate); // Could overflow!
-        account.total_rewards = account.total_rewards.wrapping_add(rewards);
+        apply_deposit(account, amount);
 
         msg!("Deposited: {}, Total: {}", amount, account.total_deposited);
         Ok(())
@@ -52,15 +42,28 @@ pub mod unsafe_arithmetic {
         amount: u64,
     ) -> Result<()> {
         let account = &mut ctx.accounts.pool;
-
-        // VULNERABILITY: No overflow check on the subtraction
-        // If amount > total_available, this wraps instead of failing
-        account.total_available = account.total_available.wrapping_sub(amount);
+        apply_withdraw(account, amount);
 
         msg!("Withdrew: {}, Remaining: {}", amount, account.total_available);
         Ok(())
     }
 
+    /// VULNERABLE: Best-effort withdrawal that never actually clamps
+    pub fn withdraw_up_to_unsafe(
+        ctx: Context<WithdrawUnsafe>,
+        requested: u64,
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.pool;
+
+        // VULNERABILITY: Meant to withdraw "as much as possible", but the
+        // requested amount is subtracted as-is. Asking for more than is
+        // available wraps total_available around to a huge number.
+        account.total_available = account.total_available.wrapping_sub(requested);
+
+        msg!("Withdrew: {}, Remaining: {}", requested, account.total_available);
+        Ok(())
+    }
+
     /// VULNERABLE: Mint new tokens with unsafe calculation
     pub fn mint_interest_unsafe(
         ctx: Context<MintInterestUnsafe>,
@@ -69,6 +72,9 @@ pub mod unsafe_arithmetic {
     ) -> Result<()> {
         let account = &mut ctx.accounts.pool;
 
+        // VULNERABILITY: No cap on interest_rate at all - any caller can
+        // mint interest at whatever rate they pass in
+
         // VULNERABILITY: Multiplication without overflow check
         // If base_amount = u64::MAX/2 and interest_rate = 3, overflow!
         let interest = base_amount.wrapping_mul(interest_rate) / 100;
@@ -78,6 +84,75 @@ pub mod unsafe_arithmetic {
         msg!("Minted interest: {}", interest);
         Ok(())
     }
+
+    /// VULNERABLE: Mint interest at `interest_rate / divisor`
+    pub fn mint_interest_with_divisor_unsafe(
+        ctx: Context<MintInterestUnsafe>,
+        base_amount: u64,
+        interest_rate: u64,
+        divisor: u64,
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.pool;
+
+        let interest = interest_with_divisor(base_amount, interest_rate, divisor);
+        account.total_minted = account.total_minted.wrapping_add(interest);
+
+        msg!("Minted interest: {}", interest);
+        Ok(())
+    }
+
+    /// VULNERABLE: Trade at whatever price the oracle reports
+    pub fn trade_unsafe(
+        ctx: Context<TradeUnsafe>,
+        amount: u64,
+    ) -> Result<()> {
+        let price = ctx.accounts.price_feed.price;
+
+        // VULNERABILITY: No sanity bound on the price. A manipulated or
+        // glitched oracle update is traded against immediately.
+        let cost = amount.wrapping_mul(price);
+
+        msg!("Traded {} at price {} (cost {})", amount, price, cost);
+        Ok(())
+    }
+}
+
+/// VULNERABLE: Pool bookkeeping for deposit_unsafe
+pub fn apply_deposit(pool: &mut Pool, amount: u64) {
+    // VULNERABILITY: Using wrapping add without overflow checks
+    // If pool.total_deposited is u64::MAX and someone deposits 1,
+    // it wraps to 0 instead of overflowing!
+    pool.total_deposited = pool.total_deposited.wrapping_add(amount);
+
+    // VULNERABILITY: Using unchecked multiplication
+    // If we calculate rewards without checking overflow:
+    let reward_rate = 100u64; // 100 basis points
+    let rewards = amount.wrapping_mul(reward_rate); // Could overflow!
+    pool.total_rewards = pool.total_rewards.wrapping_add(rewards);
+}
+
+/// VULNERABLE: Pool bookkeeping for withdraw_unsafe
+pub fn apply_withdraw(pool: &mut Pool, amount: u64) {
+    // VULNERABILITY: No overflow check on the subtraction
+    // If amount > total_available, this wraps instead of failing
+    pool.total_available = pool.total_available.wrapping_sub(amount);
+}
+
+/// VULNERABLE: Interest on `base_amount` at `interest_rate / divisor`
+pub fn interest_with_divisor(base_amount: u64, interest_rate: u64, divisor: u64) -> u64 {
+    // VULNERABILITY: `divisor` comes from the caller and is never checked.
+    // Integer division by zero panics, aborting the program with an opaque
+    // error instead of a code the client can act on.
+    base_amount.wrapping_mul(interest_rate) / divisor
+}
+
+/// VULNERABLE: Percentage change from `old` to `new` in basis points
+pub fn bps_change(old: u64, new: u64) -> u64 {
+    // VULNERABILITY: Everything stays in u64
+    // - new < old wraps around to a huge "increase"
+    // - * 10000 overflows for large prices
+    // - old == 0 divides by zero and panics
+    new.wrapping_sub(old).wrapping_mul(10_000) / old
 }
 
 #[derive(Accounts)]
@@ -98,6 +173,18 @@ pub struct MintInterestUnsafe<'info> {
     pub pool: Account<'info, Pool>,
 }
 
+#[derive(Accounts)]
+pub struct TradeUnsafe<'info> {
+    pub price_feed: Account<'info, PriceFeed>,
+
+    pub trader: Signer<'info>,
+}
+
+#[account]
+pub struct PriceFeed {
+    pub price: u64,
+}
+
 #[account]
 pub struct Pool {
     pub total_deposited: u64,
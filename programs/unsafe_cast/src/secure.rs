@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Range-Checked Conversion
+// ============================================================================
+//
+// WHAT'S FIXED:
+// The balance and the delta are both widened to i128, which holds every
+// u64 and every i64, so the sum can't wrap. The result is range-checked
+// before it is converted back:
+//
+//     below 0         ->  InsufficientBalance (a loss larger than the position)
+//     above u64::MAX  ->  ArithmeticOverflow
+//     otherwise       ->  u64::try_from(..), which can no longer fail
+//
+// BEST PRACTICES:
+// 1. Never use `as` between signed and unsigned types on untrusted values
+// 2. Do mixed-sign math in a type wide enough for both operands
+// 3. Convert back with `try_from` / `try_into`, never `as`
+// 4. Give each out-of-range direction its own error
+//
+// ============================================================================
+
+declare_id!("UnsafeCast111111111111111111111111111111111");
+
+#[program]
+pub mod unsafe_cast_secure {
+    use super::*;
+
+    /// SECURE: Settle a signed price move against the position
+    pub fn apply_price_delta_secure(
+        ctx: Context<ApplyPriceDeltaSecure>,
+        delta: i64,
+    ) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.balance = apply_delta(position.balance, delta)?;
+
+        msg!("Applied {}, balance now {}", delta, position.balance);
+        Ok(())
+    }
+}
+
+/// SECURE: `balance` moved by `delta`, failing if it would leave the u64 range
+pub fn apply_delta(balance: u64, delta: i64) -> Result<u64> {
+    // SECURE: i128 holds both operands, so this sum is exact
+    let new_balance = i128::from(balance) + i128::from(delta);
+
+    // SECURE: Explicit range check, one error per direction
+    require!(new_balance >= 0, CustomError::InsufficientBalance);
+    require!(
+        new_balance <= i128::from(u64::MAX),
+        SecurityError::ArithmeticOverflow
+    );
+
+    Ok(new_balance
+        .try_into()
+        .map_err(|_| SecurityError::ArithmeticOverflow)?)
+}
+
+#[derive(Accounts)]
+pub struct ApplyPriceDeltaSecure<'info> {
+    #[account(mut, has_one = owner @ SecurityError::Unauthorized)]
+    pub position: Account<'info, Position>,
+
+    pub owner: Signer<'info>,
+}
+
+#[account]
+pub struct Position {
+    pub owner: Pubkey,
+    pub balance: u64,
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Loss exceeds the position's balance")]
+    InsufficientBalance,
+}
@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+
+// ============================================================================
+// VULNERABILITY: Signed-to-Unsigned Cast
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// This program settles a price move against a position. The move is signed
+// - a loss is a negative `delta` - but the balance is a u64, so the code
+// converts with `as`:
+//
+//     balance = balance + (delta as u64)
+//
+// `as` never fails. It reinterprets the bits, so a negative i64 becomes a
+// huge u64:
+//
+//     -1i64 as u64 == 18_446_744_073_709_551_615 (u64::MAX)
+//
+// On an empty position, a loss of 1 becomes a gain of u64::MAX - 1.
+//
+// WHY IT'S UNSAFE:
+// - Losses turn into enormous credits; the balance explodes instead of
+//   going negative
+// - With overflow checks on, a loss on a non-empty position panics
+//   instead - the same bug, surfacing as a denial of service
+// - `as` compiles silently and hides the sign in plain sight
+//
+// SEVERITY: CRITICAL
+// ============================================================================
+
+declare_id!("UnsafeCast111111111111111111111111111111111");
+
+#[program]
+pub mod unsafe_cast {
+    use super::*;
+
+    /// VULNERABLE: Settle a signed price move against the position
+    pub fn apply_price_delta_vulnerable(
+        ctx: Context<ApplyPriceDeltaVulnerable>,
+        delta: i64,
+    ) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.balance = apply_delta(position.balance, delta);
+
+        msg!("Applied {}, balance now {}", delta, position.balance);
+        Ok(())
+    }
+}
+
+/// VULNERABLE: `balance` moved by `delta`
+pub fn apply_delta(balance: u64, delta: i64) -> u64 {
+    // VULNERABILITY: `as` turns a negative delta into a huge positive one
+    balance + (delta as u64)
+}
+
+#[derive(Accounts)]
+pub struct ApplyPriceDeltaVulnerable<'info> {
+    #[account(mut, has_one = owner)]
+    pub position: Account<'info, Position>,
+
+    pub owner: Signer<'info>,
+}
+
+#[account]
+pub struct Position {
+    pub owner: Pubkey,
+    pub balance: u64,
+}
@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+use security_utils::SecurityError;
+
+// ============================================================================
+// FIX: Zero-Copy Account Access
+// ============================================================================
+//
+// WHAT'S FIXED:
+// This version declares the pool with `#[account(zero_copy)]` and loads it
+// through `AccountLoader`:
+// - The account data is reinterpreted in place, no Borsh decoding
+// - Only the fields actually touched are read or written
+// - Cost stays flat as the deposit history grows
+//
+// BEST PRACTICES:
+// 1. Use zero_copy for accounts larger than a few hundred bytes
+// 2. Keep zero_copy structs `Pod` friendly (fixed-size arrays, no Vec/String)
+// 3. Call load()/load_mut() once per instruction and keep the borrow short
+// 4. Measure compute units before and after switching
+//
+// ============================================================================
+
+declare_id!("66666666666666666666666666666666");
+
+pub const HISTORY_LEN: usize = 512;
+
+#[program]
+pub mod zero_copy_secure {
+    use super::*;
+
+    /// SECURE: Record a deposit by writing into the account in place
+    pub fn record_deposit_zero_copy(
+        ctx: Context<RecordDepositZeroCopy>,
+        index: u32,
+        amount: u64,
+    ) -> Result<()> {
+        // SECURE: load_mut() borrows the account data directly,
+        // nothing is copied or decoded
+        let mut pool = ctx.accounts.pool.load_mut()?;
+
+        let slot = pool
+            .deposits
+            .get_mut(index as usize)
+            .ok_or(CustomError::InvalidIndex)?;
+        *slot = slot.checked_add(amount).ok_or(SecurityError::ArithmeticOverflow)?;
+
+        pool.total_deposited = pool
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(SecurityError::ArithmeticOverflow)?;
+
+        msg!("Recorded deposit {} at index {}", amount, index);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RecordDepositZeroCopy<'info> {
+    /// SECURE: AccountLoader validates owner and discriminator
+    /// but defers access to the data until load()/load_mut()
+    #[account(mut)]
+    pub pool: AccountLoader<'info, LargePool>,
+}
+
+#[account(zero_copy)]
+pub struct LargePool {
+    pub total_deposited: u64,
+    pub total_available: u64,
+    pub deposits: [u64; HISTORY_LEN],
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Deposit index out of range")]
+    InvalidIndex,
+}
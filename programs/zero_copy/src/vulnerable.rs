@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+
+// ============================================================================
+// VULNERABILITY: Deserializing Large Accounts
+// ============================================================================
+//
+// WHAT'S BROKEN:
+// This program stores a large deposit history in a regular `#[account]` and
+// loads it through `Account<'info, T>`. Every instruction deserializes the
+// entire account into memory and serializes it back on exit, even when only
+// a single entry changes.
+//
+// WHY IT'S UNSAFE:
+// - Borsh (de)serialization cost grows with account size on every call
+// - Large structs can overflow the 4KB stack frame (even boxed, they eat heap)
+// - Compute spent on copying makes transactions easier to push over budget
+// - As the history grows, the instruction eventually stops fitting at all
+//
+// SEVERITY: LOW (performance / denial of service)
+// ============================================================================
+
+declare_id!("66666666666666666666666666666666");
+
+pub const HISTORY_LEN: usize = 512;
+
+#[program]
+pub mod zero_copy {
+    use super::*;
+
+    /// VULNERABLE: Record a deposit by deserializing the whole pool
+    pub fn record_deposit_boxed(
+        ctx: Context<RecordDepositBoxed>,
+        index: u32,
+        amount: u64,
+    ) -> Result<()> {
+        // VULNERABILITY: By this point Anchor has already copied and decoded
+        // all HISTORY_LEN entries, and will re-encode them all on exit
+        let pool = &mut ctx.accounts.pool;
+
+        let slot = pool
+            .deposits
+            .get_mut(index as usize)
+            .ok_or(CustomError::InvalidIndex)?;
+        *slot = slot.checked_add(amount).ok_or(CustomError::ArithmeticOverflow)?;
+
+        pool.total_deposited = pool
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(CustomError::ArithmeticOverflow)?;
+
+        msg!("Recorded deposit {} at index {}", amount, index);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RecordDepositBoxed<'info> {
+    /// VULNERABILITY: Boxing keeps the struct off the stack,
+    /// but it is still fully deserialized on every call
+    #[account(mut)]
+    pub pool: Box<Account<'info, LargePool>>,
+}
+
+#[account]
+pub struct LargePool {
+    pub total_deposited: u64,
+    pub total_available: u64,
+    pub deposits: [u64; HISTORY_LEN],
+}
+
+#[error_code]
+pub enum CustomError {
+    #[msg("Arithmetic overflow detected")]
+    ArithmeticOverflow,
+
+    #[msg("Deposit index out of range")]
+    InvalidIndex,
+}
@@ -0,0 +1,90 @@
+// ============================================================================
+// Checked Math Core
+// ============================================================================
+//
+// The overflow-safe arithmetic behind the secure examples, with no Solana or
+// Anchor dependency and no std. Programs map MathError into their own error
+// codes; clients can call the same functions off-chain (e.g. to compute the
+// rewards they expect) and get bit-identical results.
+//
+// Being plain Rust, `cargo test -p security-math` runs on the host in
+// seconds - no BPF toolchain or validator needed.
+//
+// ============================================================================
+
+#![no_std]
+
+use core::fmt;
+
+/// Why a checked operation refused to produce a result
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MathError {
+    Overflow,
+    Underflow,
+    DivideByZero,
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MathError::Overflow => "arithmetic overflow",
+            MathError::Underflow => "arithmetic underflow",
+            MathError::DivideByZero => "division by zero",
+        })
+    }
+}
+
+/// `a + b`, or `Overflow`
+pub fn add(a: u64, b: u64) -> Result<u64, MathError> {
+    a.checked_add(b).ok_or(MathError::Overflow)
+}
+
+/// `a - b`, or `Underflow`
+pub fn sub(a: u64, b: u64) -> Result<u64, MathError> {
+    a.checked_sub(b).ok_or(MathError::Underflow)
+}
+
+/// `a * b`, or `Overflow`
+pub fn mul(a: u64, b: u64) -> Result<u64, MathError> {
+    a.checked_mul(b).ok_or(MathError::Overflow)
+}
+
+/// `a * b / denominator`, rounded down
+///
+/// The product is taken in u128, so only a final result above u64::MAX is
+/// `Overflow`. A zero `denominator` is `DivideByZero`.
+pub fn mul_div(a: u64, b: u64, denominator: u64) -> Result<u64, MathError> {
+    if denominator == 0 {
+        return Err(MathError::DivideByZero);
+    }
+    let result = a as u128 * b as u128 / denominator as u128;
+    u64::try_from(result).map_err(|_| MathError::Overflow)
+}
+
+/// `percent`% of `amount`, rounded down
+///
+/// The product is taken in u64 before dividing, so it overflows as soon as
+/// `amount * percent` does - even if the final result would fit.
+pub fn percent_of(amount: u64, percent: u64) -> Result<u64, MathError> {
+    Ok(mul(amount, percent)? / 100)
+}
+
+/// Percentage change from `old` to `new` in basis points
+///
+/// Negative when the value dropped. The difference and the x10000 scaling
+/// are done in u128, which can't overflow for any pair of u64 inputs, and
+/// a zero `old` is an error instead of a division by zero.
+pub fn bps_change(old: u64, new: u64) -> Result<i128, MathError> {
+    if old == 0 {
+        return Err(MathError::DivideByZero);
+    }
+
+    let magnitude = (new.abs_diff(old) as u128) * 10_000 / old as u128;
+
+    // magnitude < 2^78, so it always fits in i128
+    if new >= old {
+        Ok(magnitude as i128)
+    } else {
+        Ok(-(magnitude as i128))
+    }
+}
@@ -0,0 +1,46 @@
+// Host-side tests for security-math: no validator, no BPF toolchain
+
+use security_math::{add, bps_change, mul, mul_div, percent_of, sub, MathError};
+
+#[test]
+fn test_add_and_sub_at_the_boundaries() {
+    assert_eq!(add(u64::MAX - 1, 1), Ok(u64::MAX));
+    assert_eq!(add(u64::MAX, 1), Err(MathError::Overflow));
+    assert_eq!(sub(1, 1), Ok(0));
+    assert_eq!(sub(0, 1), Err(MathError::Underflow));
+}
+
+#[test]
+fn test_mul_overflow() {
+    assert_eq!(mul(u64::MAX, 1), Ok(u64::MAX));
+    assert_eq!(mul(u64::MAX / 2 + 1, 2), Err(MathError::Overflow));
+}
+
+#[test]
+fn test_mul_div() {
+    assert_eq!(mul_div(1_000, 30, 10_000), Ok(3));
+    // Rounds down
+    assert_eq!(mul_div(10, 1, 3), Ok(3));
+    // The intermediate product may exceed u64; only the result must fit
+    assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX), Ok(u64::MAX));
+    assert_eq!(mul_div(u64::MAX, 2, 1), Err(MathError::Overflow));
+    assert_eq!(mul_div(1, 1, 0), Err(MathError::DivideByZero));
+}
+
+#[test]
+fn test_percent_of() {
+    assert_eq!(percent_of(1_000, 5), Ok(50));
+    // Rounds down
+    assert_eq!(percent_of(199, 1), Ok(1));
+    // Overflows on the intermediate product, not the result
+    assert_eq!(percent_of(u64::MAX, 50), Err(MathError::Overflow));
+}
+
+#[test]
+fn test_bps_change() {
+    assert_eq!(bps_change(100, 150), Ok(5_000));
+    assert_eq!(bps_change(200, 150), Ok(-2_500));
+    assert_eq!(bps_change(1, u64::MAX), Ok((u64::MAX as i128 - 1) * 10_000));
+    assert_eq!(bps_change(u64::MAX, 0), Ok(-10_000));
+    assert_eq!(bps_change(0, 150), Err(MathError::DivideByZero));
+}
@@ -0,0 +1,111 @@
+// ============================================================================
+// SecurityError: one error taxonomy across programs
+// ============================================================================
+//
+// Programs return these for failures that mean the same thing everywhere -
+// an overflow, a held reentrancy guard, an untrusted CPI target - and keep
+// their own CustomError for anything specific to them. Clients can then
+// tell what kind of check failed from the code alone.
+//
+// CODES:
+// Each category owns a block of CATEGORY_BLOCK codes from SECURITY_ERROR_OFFSET:
+//
+//     8000  Reentrancy
+//     8100  Arithmetic
+//     8200  Authority
+//     8300  Cpi
+//     8400  Validation
+//
+// Codes are part of the interface: clients match on them. New variants go
+// at the end of their category's block, and existing ones are never moved.
+// The offset keeps them clear of program CustomErrors (6000+) and of
+// TokenOpsError (7000+).
+//
+// `#[error_code]` provides `From<SecurityError> for anchor_lang::error::Error`,
+// so these work with `require!`, `err!` and `?` like any program error.
+//
+// ============================================================================
+
+use anchor_lang::prelude::*;
+
+/// First code of the SecurityError range
+pub const SECURITY_ERROR_OFFSET: u32 = 8000;
+
+/// Codes reserved for each category
+pub const CATEGORY_BLOCK: u32 = 100;
+
+/// The vulnerability class a SecurityError guards against
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecurityCategory {
+    Reentrancy,
+    Arithmetic,
+    Authority,
+    Cpi,
+    Validation,
+}
+
+#[error_code(offset = 8000)]
+pub enum SecurityError {
+    // Reentrancy: 8000..8100
+    #[msg("Reentrancy guard is held")]
+    ReentrancyGuardHeld = 0,
+
+    // Arithmetic: 8100..8200
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow = 100,
+
+    #[msg("Arithmetic underflow")]
+    ArithmeticUnderflow,
+
+    #[msg("Division by zero")]
+    DivideByZero,
+
+    // Authority: 8200..8300
+    #[msg("Signer is not authorized for this account")]
+    Unauthorized = 200,
+
+    #[msg("A required signature is missing")]
+    MissingSignature,
+
+    // Cpi: 8300..8400
+    #[msg("CPI target is not a trusted program")]
+    UntrustedProgram = 300,
+
+    #[msg("CPI target is not executable")]
+    ProgramNotExecutable,
+
+    // Validation: 8400..8500
+    #[msg("Account is owned by the wrong program")]
+    WrongAccountOwner = 400,
+
+    #[msg("Input exceeds the maximum length")]
+    InputTooLong,
+
+    #[msg("Invalid amount")]
+    InvalidAmount,
+
+    #[msg("The same account was passed where distinct accounts are required")]
+    DuplicateAccount,
+}
+
+impl SecurityError {
+    /// The category whose code block this variant belongs to
+    pub fn category(self) -> SecurityCategory {
+        match self {
+            SecurityError::ReentrancyGuardHeld => SecurityCategory::Reentrancy,
+            SecurityError::ArithmeticOverflow
+            | SecurityError::ArithmeticUnderflow
+            | SecurityError::DivideByZero => SecurityCategory::Arithmetic,
+            SecurityError::Unauthorized | SecurityError::MissingSignature => {
+                SecurityCategory::Authority
+            }
+            SecurityError::UntrustedProgram | SecurityError::ProgramNotExecutable => {
+                SecurityCategory::Cpi
+            }
+            SecurityError::WrongAccountOwner
+            | SecurityError::InputTooLong
+            | SecurityError::InvalidAmount
+            | SecurityError::DuplicateAccount => SecurityCategory::Validation,
+        }
+    }
+}
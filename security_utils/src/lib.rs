@@ -0,0 +1,18 @@
+// ============================================================================
+// Shared Security Utilities
+// ============================================================================
+//
+// Reusable building blocks for the secure examples. These are plain library
+// code (no program, no entrypoint): the secure programs depend on them so a
+// pattern is implemented - and reviewed - once.
+//
+// ============================================================================
+
+pub mod error;
+pub mod pda;
+pub mod safe_math;
+pub mod token_ops;
+pub mod validate;
+
+pub use error::{SecurityCategory, SecurityError};
+pub use token_ops::{TokenOps, TokenOpsError};
@@ -0,0 +1,62 @@
+// ============================================================================
+// pda: namespaced PDA derivation
+// ============================================================================
+//
+// Every PDA derived through this module starts with a namespace seed and is
+// followed only by 32-byte keys:
+//
+//     [namespace, key]             derive
+//     [namespace, first, second]   derive_pair
+//
+// The namespace is "<program>/<kind>", e.g. "reentrancy_risk/pool_signer":
+// the program prefix keeps the example programs from sharing a seed prefix,
+// and the kind keeps account kinds apart within one program. Since the
+// namespace is the only seed whose length varies, two derivations can only
+// produce the same seed bytes if both the namespace AND the keys are equal -
+// the boundary problem shown in programs/seed_collision can't happen here.
+//
+// A namespace is a single seed, so it is at most MAX_SEED_LEN (32) bytes.
+// A longer one is an InputTooLong error, not a panic inside the runtime.
+//
+// Program-wide singletons have no natural key; they use the program id.
+//
+// ============================================================================
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::MAX_SEED_LEN;
+
+use crate::SecurityError;
+
+/// Derives the PDA for `key` under `namespace`, returning it with its bump
+///
+/// Equivalent to an Anchor `seeds = [namespace.as_bytes(), key.as_ref()]`
+/// constraint, so handler code and account constraints agree.
+pub fn derive(namespace: &str, key: &Pubkey, program: &Pubkey) -> Result<(Pubkey, u8)> {
+    check_namespace(namespace)?;
+    Ok(Pubkey::find_program_address(
+        &[namespace.as_bytes(), key.as_ref()],
+        program,
+    ))
+}
+
+/// Derives the PDA for the `(first, second)` pair under `namespace`, e.g. a
+/// per-user record in a pool
+///
+/// Equivalent to `seeds = [namespace.as_bytes(), first.as_ref(), second.as_ref()]`.
+pub fn derive_pair(
+    namespace: &str,
+    first: &Pubkey,
+    second: &Pubkey,
+    program: &Pubkey,
+) -> Result<(Pubkey, u8)> {
+    check_namespace(namespace)?;
+    Ok(Pubkey::find_program_address(
+        &[namespace.as_bytes(), first.as_ref(), second.as_ref()],
+        program,
+    ))
+}
+
+fn check_namespace(namespace: &str) -> Result<()> {
+    require!(namespace.len() <= MAX_SEED_LEN, SecurityError::InputTooLong);
+    Ok(())
+}
@@ -0,0 +1,57 @@
+// ============================================================================
+// safe_math: checked u64 arithmetic for handlers
+// ============================================================================
+//
+// Thin wrappers over the security_math core that return an Anchor `Result`,
+// so a handler can write
+//
+//     pool.total = safe_add(pool.total, amount)?;
+//
+// instead of repeating `.checked_add(..).ok_or(..)?` with its own error at
+// every step. Failures are SecurityErrors in the Arithmetic block:
+//
+//     Overflow      ->  ArithmeticOverflow
+//     Underflow     ->  ArithmeticUnderflow
+//     DivideByZero  ->  DivideByZero
+//
+// and reach the runtime as the matching `ProgramError::Custom` code.
+//
+// ============================================================================
+
+use anchor_lang::prelude::*;
+use security_math::{self as math, MathError};
+
+use crate::SecurityError;
+
+impl From<MathError> for SecurityError {
+    fn from(err: MathError) -> Self {
+        match err {
+            MathError::Overflow => SecurityError::ArithmeticOverflow,
+            MathError::Underflow => SecurityError::ArithmeticUnderflow,
+            MathError::DivideByZero => SecurityError::DivideByZero,
+        }
+    }
+}
+
+/// `a + b`, or ArithmeticOverflow
+pub fn safe_add(a: u64, b: u64) -> Result<u64> {
+    Ok(math::add(a, b).map_err(SecurityError::from)?)
+}
+
+/// `a - b`, or ArithmeticUnderflow
+pub fn safe_sub(a: u64, b: u64) -> Result<u64> {
+    Ok(math::sub(a, b).map_err(SecurityError::from)?)
+}
+
+/// `a * b`, or ArithmeticOverflow
+pub fn safe_mul(a: u64, b: u64) -> Result<u64> {
+    Ok(math::mul(a, b).map_err(SecurityError::from)?)
+}
+
+/// `a * b / denominator`, rounded down
+///
+/// The product is taken in u128, so it's only ArithmeticOverflow if the
+/// final result doesn't fit in a u64. A zero `denominator` is DivideByZero.
+pub fn safe_mul_div(a: u64, b: u64, denominator: u64) -> Result<u64> {
+    Ok(math::mul_div(a, b, denominator).map_err(SecurityError::from)?)
+}
@@ -0,0 +1,165 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, MintTo, Token, Transfer, TransferChecked};
+
+// ============================================================================
+// TokenOps: validated SPL Token CPIs
+// ============================================================================
+//
+// Every method checks that the account being invoked really is the SPL Token
+// program (right id AND executable) before building the CPI, so a spoofed
+// program can never receive the authority's signature. Implemented for both
+// `Program<'info, Token>` and a raw `AccountInfo`, so handlers that take the
+// program as an unchecked account get the same guarantees.
+//
+// Pass `&[]` as `signer_seeds` unless the authority is a PDA.
+//
+// ============================================================================
+
+pub trait TokenOps<'info> {
+    /// The account that will be invoked as the token program
+    fn token_program_info(&self) -> AccountInfo<'info>;
+
+    /// Returns the token program account after checking it is the real one
+    fn validated_token_program(&self) -> Result<AccountInfo<'info>> {
+        let program = self.token_program_info();
+        require_keys_eq!(program.key(), token::ID, TokenOpsError::InvalidTokenProgram);
+        require!(program.executable, TokenOpsError::ProgramNotExecutable);
+        Ok(program)
+    }
+
+    /// Transfers `amount` tokens from `from` to `to`
+    fn transfer(
+        &self,
+        from: AccountInfo<'info>,
+        to: AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let program = self.validated_token_program()?;
+        token::transfer(
+            CpiContext::new_with_signer(
+                program,
+                Transfer {
+                    from,
+                    to,
+                    authority,
+                },
+                signer_seeds,
+            ),
+            amount,
+        )
+        .map_err(|e| log_failure("transfer", e))
+    }
+
+    /// Transfers `amount` tokens of `mint`, failing unless `decimals` matches it
+    ///
+    /// The token program also checks both accounts belong to `mint`, so a
+    /// caller that has the wrong mint in mind can't move the wrong asset.
+    #[allow(clippy::too_many_arguments)]
+    fn transfer_checked(
+        &self,
+        from: AccountInfo<'info>,
+        mint: AccountInfo<'info>,
+        to: AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+        amount: u64,
+        decimals: u8,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let program = self.validated_token_program()?;
+        token::transfer_checked(
+            CpiContext::new_with_signer(
+                program,
+                TransferChecked {
+                    from,
+                    mint,
+                    to,
+                    authority,
+                },
+                signer_seeds,
+            ),
+            amount,
+            decimals,
+        )
+        .map_err(|e| log_failure("transfer_checked", e))
+    }
+
+    /// Mints `amount` new tokens of `mint` into `to`
+    fn mint_to(
+        &self,
+        mint: AccountInfo<'info>,
+        to: AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let program = self.validated_token_program()?;
+        token::mint_to(
+            CpiContext::new_with_signer(
+                program,
+                MintTo {
+                    mint,
+                    to,
+                    authority,
+                },
+                signer_seeds,
+            ),
+            amount,
+        )
+        .map_err(|e| log_failure("mint_to", e))
+    }
+
+    /// Burns `amount` tokens of `mint` from `from`
+    fn burn(
+        &self,
+        mint: AccountInfo<'info>,
+        from: AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let program = self.validated_token_program()?;
+        token::burn(
+            CpiContext::new_with_signer(
+                program,
+                Burn {
+                    mint,
+                    from,
+                    authority,
+                },
+                signer_seeds,
+            ),
+            amount,
+        )
+        .map_err(|e| log_failure("burn", e))
+    }
+}
+
+impl<'info> TokenOps<'info> for AccountInfo<'info> {
+    fn token_program_info(&self) -> AccountInfo<'info> {
+        self.clone()
+    }
+}
+
+impl<'info> TokenOps<'info> for Program<'info, Token> {
+    fn token_program_info(&self) -> AccountInfo<'info> {
+        self.to_account_info()
+    }
+}
+
+/// Logs which token operation failed, then passes the error through
+fn log_failure(operation: &str, error: Error) -> Error {
+    msg!("Token {} CPI failed: {:?}", operation, error);
+    error
+}
+
+// Offset keeps these codes clear of each program's own CustomError (6000+)
+#[error_code(offset = 7000)]
+pub enum TokenOpsError {
+    #[msg("Account is not the SPL Token program")]
+    InvalidTokenProgram,
+
+    #[msg("Token program account is not executable")]
+    ProgramNotExecutable,
+}
@@ -0,0 +1,34 @@
+// ============================================================================
+// validate!: ordered precondition checks
+// ============================================================================
+//
+// Chains `condition => error` pairs and evaluates to `anchor_lang::Result<()>`.
+// Conditions are checked in order; the first one that is false produces its
+// error and nothing after it is evaluated. Use with `?`:
+//
+//     validate!(
+//         user.balance >= amount => CustomError::InsufficientBalance,
+//         !pool.locked => CustomError::PoolLocked,
+//     )?;
+//
+// Equivalent to a run of `require!` calls, but reads as one block of
+// preconditions and can be tested as a value.
+//
+// ============================================================================
+
+#[macro_export]
+macro_rules! validate {
+    ($($condition:expr => $error:expr),+ $(,)?) => {{
+        let result: ::anchor_lang::Result<()> = Ok(());
+        $(
+            let result = result.and_then(|_| {
+                if $condition {
+                    Ok(())
+                } else {
+                    Err(::anchor_lang::error!($error))
+                }
+            });
+        )+
+        result
+    }};
+}
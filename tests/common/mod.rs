@@ -0,0 +1,303 @@
+//! Helpers shared by the integration tests
+//!
+//! Each program is loaded with `setup_program_test!`, and the SPL fixtures
+//! (`create_mint`, `create_token_account`, `mint_to`) return the address of
+//! whatever they create, so a test only wires the pieces together.
+
+use anchor_lang::{AccountDeserialize, AccountSerialize, AnchorDeserialize, Discriminator};
+use anchor_spl::token::spl_token;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_program_test::{BanksClientError, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+
+/// Builds a `ProgramTest` that runs `program`'s entrypoint natively
+///
+/// `setup_program_test!("slippage", secure)` loads `secure::entry` under
+/// `secure::ID`. `name` is the crate name, which `ProgramTest` uses to find
+/// the compiled `.so` under `cargo test-sbf`.
+macro_rules! setup_program_test {
+    ($name:literal, $($program:ident)::+) => {
+        ::solana_program_test::ProgramTest::new(
+            $name,
+            $($program)::+::ID,
+            ::solana_program_test::processor!($($program)::+::entry),
+        )
+    };
+}
+pub(crate) use setup_program_test;
+
+/// Builds a raw account holding an Anchor `#[account]` (discriminator + data)
+pub fn anchor_account<T: AccountSerialize>(owner: &Pubkey, state: &T) -> Account {
+    let mut data = Vec::new();
+    state.try_serialize(&mut data).unwrap();
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Reads back and deserializes an Anchor account
+pub async fn load<T: AccountDeserialize>(ctx: &mut ProgramTestContext, address: Pubkey) -> T {
+    let account = ctx.banks_client.get_account(address).await.unwrap().unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+/// Sends a single instruction, paid for by the context payer
+pub async fn process(
+    ctx: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    process_all(ctx, &[instruction], signers).await
+}
+
+/// Sends several instructions in one transaction
+pub async fn process_all(
+    ctx: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut all_signers = vec![&ctx.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&ctx.payer.pubkey()),
+        &all_signers,
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(tx).await
+}
+
+/// Reads the lamport balance of `address`, 0 if it doesn't exist
+pub async fn lamports(ctx: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    ctx.banks_client.get_balance(address).await.unwrap()
+}
+
+/// Transfers `lamports` from the context payer to `address`
+pub async fn fund(ctx: &mut ProgramTestContext, address: &Pubkey, lamports: u64) {
+    let ix = system_instruction::transfer(&ctx.payer.pubkey(), address, lamports);
+    process(ctx, ix, &[]).await.unwrap();
+}
+
+/// Creates an SPL mint controlled by `authority`
+pub async fn create_mint(ctx: &mut ProgramTestContext, authority: &Pubkey) -> Pubkey {
+    create_mint_with_freeze_authority(ctx, authority, None).await
+}
+
+/// Like `create_mint`, with `freeze_authority` able to freeze its accounts
+pub async fn create_mint_with_freeze_authority(
+    ctx: &mut ProgramTestContext,
+    authority: &Pubkey,
+    freeze_authority: Option<&Pubkey>,
+) -> Pubkey {
+    let mint = Keypair::new();
+    let len = spl_token::state::Mint::LEN;
+    let instructions = [
+        system_instruction::create_account(
+            &ctx.payer.pubkey(),
+            &mint.pubkey(),
+            Rent::default().minimum_balance(len),
+            len as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(
+            &spl_token::ID,
+            &mint.pubkey(),
+            authority,
+            freeze_authority,
+            6,
+        )
+        .unwrap(),
+    ];
+    process_all(ctx, &instructions, &[&mint]).await.unwrap();
+    mint.pubkey()
+}
+
+/// Creates an SPL token account for `mint` owned by `owner`
+pub async fn create_token_account(
+    ctx: &mut ProgramTestContext,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Pubkey {
+    let account = Keypair::new();
+    let len = spl_token::state::Account::LEN;
+    let instructions = [
+        system_instruction::create_account(
+            &ctx.payer.pubkey(),
+            &account.pubkey(),
+            Rent::default().minimum_balance(len),
+            len as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_account3(
+            &spl_token::ID,
+            &account.pubkey(),
+            mint,
+            owner,
+        )
+        .unwrap(),
+    ];
+    process_all(ctx, &instructions, &[&account]).await.unwrap();
+    account.pubkey()
+}
+
+/// Mints `amount` tokens into `account`
+pub async fn mint_to(
+    ctx: &mut ProgramTestContext,
+    mint: &Pubkey,
+    account: &Pubkey,
+    authority: &Keypair,
+    amount: u64,
+) {
+    let ix = spl_token::instruction::mint_to(
+        &spl_token::ID,
+        mint,
+        account,
+        &authority.pubkey(),
+        &[],
+        amount,
+    )
+    .unwrap();
+    process(ctx, ix, &[authority]).await.unwrap();
+}
+
+/// Reads the balance of an SPL token account
+pub async fn token_balance(ctx: &mut ProgramTestContext, account: Pubkey) -> u64 {
+    let account = ctx.banks_client.get_account(account).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
+
+/// Sends a single instruction and also returns the transaction logs
+pub async fn process_with_logs(
+    ctx: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> (Result<(), TransactionError>, Vec<String>) {
+    let mut all_signers = vec![&ctx.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&ctx.payer.pubkey()),
+        &all_signers,
+        ctx.last_blockhash,
+    );
+    let result = ctx
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await
+        .unwrap();
+    (result.result, result.metadata.unwrap().log_messages)
+}
+
+/// Sends a single instruction and also returns what it set with
+/// `set_return_data`, as `(program_id, data)`
+pub async fn process_with_return_data(
+    ctx: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> (Result<(), TransactionError>, Option<(Pubkey, Vec<u8>)>) {
+    let mut all_signers = vec![&ctx.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&ctx.payer.pubkey()),
+        &all_signers,
+        ctx.last_blockhash,
+    );
+    let result = ctx
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await
+        .unwrap();
+    let return_data = result
+        .metadata
+        .unwrap()
+        .return_data
+        .map(|return_data| (return_data.program_id, return_data.data));
+    (result.result, return_data)
+}
+
+/// Decodes the first Anchor event of type `T` found in the logs
+///
+/// `emit!` logs through `sol_log_data`, which only reaches the
+/// transaction logs when the program runs as SBF (`cargo test-sbf`).
+pub fn find_event<T: AnchorDeserialize + Discriminator>(logs: &[String]) -> Option<T> {
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| STANDARD.decode(data).ok())
+        .find(|bytes| bytes.starts_with(&T::discriminator()))
+        .map(|bytes| T::deserialize(&mut &bytes[8..]).unwrap())
+}
+
+/// Asserts some line of the transaction logs contains `expected`
+pub fn assert_log_contains(logs: &[String], expected: &str) {
+    assert!(
+        logs.iter().any(|log| log.contains(expected)),
+        "no log contains {:?}: {:#?}",
+        expected,
+        logs
+    );
+}
+
+/// Asserts a transaction failed with the given Anchor error code
+pub fn assert_custom_error(result: Result<(), BanksClientError>, expected: u32) {
+    match result.expect_err("transaction should have failed").unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            assert_eq!(code, expected, "unexpected error code")
+        }
+        other => panic!("expected custom error {}, got {:?}", expected, other),
+    }
+}
+
+/// Account fields captured by `snapshot!`, as `("name.field", value)`
+pub type Snapshot = Vec<(&'static str, String)>;
+
+/// Captures the fields of one or more account states
+///
+/// `snapshot!(pool: PoolSafe = state => { total_deposited, .. })` lists
+/// fields by name. The state is destructured without `..`, so a field
+/// added to the account later stops this compiling until the snapshot
+/// covers it - nothing can change unobserved.
+macro_rules! snapshot {
+    ($($name:ident: $ty:path = $state:expr => { $($field:ident),+ $(,)? }),+ $(,)?) => {{
+        let mut fields: $crate::common::Snapshot = Vec::new();
+        $(
+            let $ty { $($field),+ } = &$state;
+            $(
+                fields.push((
+                    concat!(stringify!($name), ".", stringify!($field)),
+                    format!("{:?}", $field),
+                ));
+            )+
+        )+
+        fields
+    }};
+}
+pub(crate) use snapshot;
+
+/// Names of the fields whose values differ between two snapshots
+pub fn changed_fields(before: &Snapshot, after: &Snapshot) -> Vec<&'static str> {
+    assert_eq!(
+        before.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        after.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        "snapshots cover different fields"
+    );
+    before
+        .iter()
+        .zip(after)
+        .filter(|(old, new)| old.1 != new.1)
+        .map(|(old, _)| old.0)
+        .collect()
+}
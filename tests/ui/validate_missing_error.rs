@@ -0,0 +1,6 @@
+// Every condition needs an error to report
+use security_utils::validate;
+
+fn main() {
+    let _ = validate!(1 > 0);
+}
@@ -0,0 +1,24 @@
+// validate! accepts one or more `condition => error` pairs, with or without
+// a trailing comma, and evaluates to anchor_lang::Result<()>
+use anchor_lang::prelude::*;
+use security_utils::validate;
+
+#[error_code]
+pub enum CustomError {
+    First,
+    Second,
+}
+
+fn check(a: u64, b: u64) -> Result<()> {
+    validate!(a > 0 => CustomError::First)?;
+    validate!(
+        a > 0 => CustomError::First,
+        b >= a => CustomError::Second,
+    )?;
+    Ok(())
+}
+
+fn main() {
+    assert!(check(1, 2).is_ok());
+    assert!(check(2, 1).is_err());
+}